@@ -55,5 +55,7 @@ async fn main() -> Result<(), Error> {
         .init();
 
     let octox = Octox::new().workflow(HelloWorld::constructor)?;
-    octox.serve().await
+    octox.serve().await?;
+
+    Ok(())
 }
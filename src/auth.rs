@@ -1,9 +1,7 @@
 use axum::body::Bytes;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use axum::Json;
 use hmac::{Hmac, Mac};
-use serde_json::json;
 use sha2::Sha256;
 use thiserror::Error;
 
@@ -11,6 +9,17 @@ use crate::WebhookSecret;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// Whether the webhook route verifies the signatures of deliveries
+///
+/// Verification can only be skipped in the development environment, e.g. to replay recorded
+/// deliveries without the webhook secret.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) enum SignatureVerification {
+    #[default]
+    Required,
+    Skipped,
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Error)]
 pub enum AuthError {
     #[error("missing {0} header")]
@@ -27,27 +36,49 @@ pub enum AuthError {
     UnexpectedPayload,
 }
 
-impl IntoResponse for AuthError {
-    fn into_response(self) -> Response {
-        let message = self.to_string();
+impl AuthError {
+    /// Stable, machine-readable code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingHeader(_) => "missing_header",
+            AuthError::FailedHmacInitialization => "failed_hmac_initialization",
+            AuthError::WrongSignatureFormat => "wrong_signature_format",
+            AuthError::FailedDecodingSignature => "failed_decoding_signature",
+            AuthError::InvalidSignature => "invalid_signature",
+            AuthError::UnexpectedPayload => "unexpected_payload",
+        }
+    }
 
-        let status = match self {
+    pub fn status(&self) -> StatusCode {
+        match self {
             AuthError::MissingHeader(_) => StatusCode::BAD_REQUEST,
             AuthError::FailedHmacInitialization => StatusCode::INTERNAL_SERVER_ERROR,
             AuthError::WrongSignatureFormat => StatusCode::BAD_REQUEST,
             AuthError::FailedDecodingSignature => StatusCode::INTERNAL_SERVER_ERROR,
             AuthError::InvalidSignature => StatusCode::UNAUTHORIZED,
             AuthError::UnexpectedPayload => StatusCode::BAD_REQUEST,
-        };
-
-        let body = Json(json!({
-            "error": message,
-        }));
+        }
+    }
+}
 
-        (status, body).into_response()
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        crate::Error::from(self).into_response()
     }
 }
 
+/// Computes the value of the `X-Hub-Signature-256` header for the body
+pub fn sign(body: &[u8], secret: &WebhookSecret) -> Result<String, AuthError> {
+    let mut hmac = HmacSha256::new_from_slice(secret.get().as_bytes())
+        .map_err(|_| AuthError::FailedHmacInitialization)?;
+    hmac.update(body);
+
+    Ok(format!(
+        "sha256={}",
+        hex::encode(hmac.finalize().into_bytes())
+    ))
+}
+
 pub fn verify_signature(
     body: &Bytes,
     signature: &str,
@@ -82,7 +113,17 @@ mod tests {
 
     use crate::WebhookSecret;
 
-    use super::verify_signature;
+    use super::{sign, verify_signature};
+
+    #[test]
+    fn sign_computes_signature() {
+        let secret = WebhookSecret::new("verify_signature".into());
+
+        assert_eq!(
+            "sha256=22568b39613009e6d1b1fd063085c05063998bda5243a597c0cc524e044990ae",
+            sign(b"verify_signature", &secret).unwrap()
+        );
+    }
 
     #[test]
     fn verify_signature_with_valid_signature() {
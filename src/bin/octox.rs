@@ -0,0 +1,371 @@
+//! Command line tool to operate an octox app
+//!
+//! The tool reads the same configuration as the app, from a config file and `OCTOX_*` environment
+//! variables. It requires the `cli` feature.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use argh::FromArgs;
+use futures::{StreamExt, TryStreamExt};
+use github_parts::github::WebhookSecret;
+use reqwest::Method;
+use serde_json::Value;
+
+use octox::client::GitHubClient;
+use octox::redelivery::{is_failed_status, Redelivery};
+use octox::testing::Fixture;
+use octox::{Error, OctoxConfig, ValidatedConfig};
+
+/// Number of recent deliveries that `deliveries list` scans, which bounds the pages it requests
+const MAX_SCANNED_DELIVERIES: usize = 1000;
+
+#[derive(Debug, FromArgs)]
+/// Operate an octox app.
+struct Cli {
+    /// path of the config file, in TOML or YAML format
+    #[argh(option, short = 'c')]
+    config: Option<PathBuf>,
+
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Sign(SignCommand),
+    Send(SendCommand),
+    Jwt(JwtCommand),
+    Token(TokenCommand),
+    CheckConfig(CheckConfigCommand),
+    Deliveries(DeliveriesCommand),
+}
+
+#[derive(Debug, FromArgs)]
+/// Compute the X-Hub-Signature-256 header for a payload.
+#[argh(subcommand, name = "sign")]
+struct SignCommand {
+    /// file with the payload
+    #[argh(positional)]
+    file: PathBuf,
+
+    /// webhook secret, which defaults to the configured secret
+    #[argh(option)]
+    secret: Option<String>,
+}
+
+#[derive(Debug, FromArgs)]
+/// Send a payload to a running server with the headers that GitHub sends.
+#[argh(subcommand, name = "send")]
+struct SendCommand {
+    /// file with the payload
+    #[argh(positional)]
+    file: PathBuf,
+
+    /// event type, which defaults to the start of the file name, e.g. `check_run` for
+    /// `check_run.created.json`
+    #[argh(option, short = 'e')]
+    event: Option<String>,
+
+    /// URL of the webhook route
+    #[argh(option, default = "String::from(\"http://127.0.0.1:3000/\")")]
+    url: String,
+
+    /// id of the delivery, which defaults to a random id
+    #[argh(option)]
+    delivery_id: Option<String>,
+
+    /// webhook secret, which defaults to the configured secret
+    #[argh(option)]
+    secret: Option<String>,
+}
+
+#[derive(Debug, FromArgs)]
+/// Mint a JWT that authenticates as the app.
+#[argh(subcommand, name = "jwt")]
+struct JwtCommand {}
+
+#[derive(Debug, FromArgs)]
+/// Mint an access token for an installation of the app.
+#[argh(subcommand, name = "token")]
+struct TokenCommand {
+    /// id of the installation
+    #[argh(positional)]
+    installation: u64,
+}
+
+#[derive(Debug, FromArgs)]
+/// Validate the configuration and report all problems.
+#[argh(subcommand, name = "check-config")]
+struct CheckConfigCommand {}
+
+#[derive(Debug, FromArgs)]
+/// List and redeliver the app's webhook deliveries.
+#[argh(subcommand, name = "deliveries")]
+struct DeliveriesCommand {
+    #[argh(subcommand)]
+    command: DeliveriesSubcommand,
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum DeliveriesSubcommand {
+    List(ListDeliveriesCommand),
+    Redeliver(RedeliverCommand),
+}
+
+#[derive(Debug, FromArgs)]
+/// List the most recent deliveries.
+#[argh(subcommand, name = "list")]
+struct ListDeliveriesCommand {
+    /// maximum number of deliveries
+    #[argh(option, default = "30")]
+    limit: usize,
+
+    /// only list deliveries that timed out or failed with a server error, among the 1000 most
+    /// recent deliveries
+    #[argh(switch)]
+    failed: bool,
+}
+
+#[derive(Debug, FromArgs)]
+/// Redeliver deliveries by id, or all deliveries that failed.
+#[argh(subcommand, name = "redeliver")]
+struct RedeliverCommand {
+    /// ids of the deliveries
+    #[argh(positional)]
+    ids: Vec<u64>,
+
+    /// redeliver the deliveries of the past three days whose attempts have all failed
+    #[argh(switch)]
+    failed: bool,
+
+    /// maximum number of failed deliveries to redeliver
+    #[argh(option, default = "50")]
+    max: usize,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let cli: Cli = argh::from_env();
+
+    if let Err(error) = run(cli).await {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Error> {
+    let config = OctoxConfig::load(cli.config.as_deref())?;
+
+    match cli.command {
+        Command::Sign(command) => {
+            let fixture = Fixture::load("", &command.file)?;
+            let secret = webhook_secret(&config, command.secret)?;
+
+            println!("{}", fixture.signature(&secret)?);
+        }
+        Command::Send(command) => send(&config, command).await?,
+        Command::Jwt(_) => println!("{}", github_client(&config)?.app_token()?),
+        Command::Token(command) => {
+            let token = github_client(&config)?
+                .installation_token(command.installation)
+                .await?;
+
+            println!("{}", token);
+        }
+        Command::CheckConfig(_) => check_config(&config)?,
+        Command::Deliveries(command) => match command.command {
+            DeliveriesSubcommand::List(command) => list_deliveries(&config, command).await?,
+            DeliveriesSubcommand::Redeliver(command) => redeliver(&config, command).await?,
+        },
+    }
+
+    Ok(())
+}
+
+async fn send(config: &OctoxConfig, command: SendCommand) -> Result<(), Error> {
+    let event = match command.event {
+        Some(event) => event,
+        None => event_from_file_name(&command.file)
+            .ok_or_else(|| Error::Configuration("event type must be set with --event".into()))?,
+    };
+    let delivery_id = command.delivery_id.unwrap_or_else(random_delivery_id);
+
+    let fixture = Fixture::load(&event, &command.file)?;
+    let secret = webhook_secret(config, command.secret)?;
+
+    let response = reqwest::Client::new()
+        .post(&command.url)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "GitHub-Hookshot/octox")
+        .header("X-GitHub-Event", &event)
+        .header("X-GitHub-Delivery", &delivery_id)
+        .header("X-Hub-Signature-256", fixture.signature(&secret)?)
+        .body(fixture.payload().clone())
+        .send()
+        .await?;
+
+    let status = response.status();
+    println!("{} {}", delivery_id, status);
+    println!("{}", response.text().await?);
+
+    if !status.is_success() {
+        return Err(anyhow!("server responded with {}", status).into());
+    }
+
+    Ok(())
+}
+
+fn check_config(config: &OctoxConfig) -> Result<(), Error> {
+    let config = config.validate()?;
+
+    println!("configuration is valid");
+    println!("  github host:    {}", config.github_host.get());
+    println!("  app id:         {}", config.app_id.get());
+    println!("  socket address: {}", config.socket_address);
+    println!("  webhook path:   {}", config.paths.webhook);
+    println!("  environment:    {}", config.environment.as_str());
+
+    Ok(())
+}
+
+async fn list_deliveries(
+    config: &OctoxConfig,
+    command: ListDeliveriesCommand,
+) -> Result<(), Error> {
+    let deliveries: Vec<Value> = github_client(config)?
+        .paginate::<Value>("app/hook/deliveries?per_page=100")
+        .take(MAX_SCANNED_DELIVERIES)
+        .try_filter(|delivery| {
+            let status_code = delivery["status_code"].as_u64().unwrap_or(0);
+            let failed = is_failed_status(u16::try_from(status_code).unwrap_or(u16::MAX));
+            futures::future::ready(!command.failed || failed)
+        })
+        .take(command.limit)
+        .try_collect()
+        .await?;
+
+    for delivery in deliveries {
+        println!(
+            "{}\t{}\t{}\t{}\t{}.{}",
+            delivery["id"],
+            delivery["guid"].as_str().unwrap_or_default(),
+            delivery["delivered_at"].as_str().unwrap_or_default(),
+            delivery["status_code"],
+            delivery["event"].as_str().unwrap_or_default(),
+            delivery["action"].as_str().unwrap_or("-"),
+        );
+    }
+
+    Ok(())
+}
+
+async fn redeliver(config: &OctoxConfig, command: RedeliverCommand) -> Result<(), Error> {
+    let github_client = github_client(config)?;
+
+    if command.failed {
+        let report = Redelivery::new(github_client.clone())
+            .max_redeliveries(command.max)
+            .run()
+            .await?;
+
+        println!(
+            "scanned {} deliveries, redelivered {}, failed {}, deferred {}",
+            report.scanned,
+            report.redelivered.len(),
+            report.failed.len(),
+            report.deferred
+        );
+    }
+
+    for id in command.ids {
+        github_client
+            .request::<(), Value>(
+                Method::POST,
+                &format!("app/hook/deliveries/{}/attempts", id),
+                None,
+            )
+            .await?;
+
+        println!("redelivered {}", id);
+    }
+
+    Ok(())
+}
+
+fn github_client(config: &OctoxConfig) -> Result<GitHubClient, Error> {
+    let config: ValidatedConfig = config.validate()?;
+
+    Ok(GitHubClient::new(
+        config.github_host,
+        config.app_id,
+        config.private_key,
+    ))
+}
+
+/// Returns the secret from the command line, or validates the configuration to get it
+fn webhook_secret(config: &OctoxConfig, secret: Option<String>) -> Result<WebhookSecret, Error> {
+    match secret {
+        Some(secret) => Ok(WebhookSecret::new(secret)),
+        None => Ok(config.validate()?.webhook_secret),
+    }
+}
+
+fn event_from_file_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let event = name.split('.').next()?;
+
+    (!event.is_empty()).then(|| event.into())
+}
+
+/// Returns an id in the format of GitHub's delivery ids, which are UUIDs
+fn random_delivery_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let seed = nanos ^ ((std::process::id() as u128) << 64);
+    let hex = format!(
+        "{:032x}",
+        seed.wrapping_mul(0x9e3779b97f4a7c15f39cc0605cedc835)
+    );
+
+    format!(
+        "{}-{}-4{}-8{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[13..16],
+        &hex[17..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{event_from_file_name, random_delivery_id};
+
+    #[test]
+    fn event_from_file_name_uses_first_part_of_name() {
+        assert_eq!(
+            Some("check_run".into()),
+            event_from_file_name(Path::new("tests/fixtures/check_run.created.json"))
+        );
+        assert_eq!(None, event_from_file_name(Path::new(".json")));
+    }
+
+    #[test]
+    fn random_delivery_id_has_uuid_format() {
+        let id = random_delivery_id();
+
+        assert_eq!(36, id.len());
+        assert_eq!(
+            vec![8, 4, 4, 4, 12],
+            id.split('-').map(str::len).collect::<Vec<_>>()
+        );
+    }
+}
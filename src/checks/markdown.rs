@@ -0,0 +1,153 @@
+use std::fmt::{Display, Formatter};
+
+/// Builder for Markdown documents
+///
+/// The summary and text of a check run's output are rendered as Markdown by GitHub. The builder
+/// makes it easy to assemble them from common blocks without worrying about blank lines between
+/// blocks.
+///
+/// Headings, paragraphs, list items and summaries are inserted as they are, so that they can
+/// contain inline Markdown like links or emphasis. Text from untrusted sources must be escaped
+/// before it is passed to them. Table cells and code blocks are escaped by the builder.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Markdown {
+    blocks: Vec<String>,
+}
+
+impl Markdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn heading(mut self, level: usize, text: &str) -> Self {
+        let level = level.clamp(1, 6);
+        self.blocks.push(format!("{} {}", "#".repeat(level), text));
+        self
+    }
+
+    pub fn paragraph(mut self, text: &str) -> Self {
+        self.blocks.push(text.into());
+        self
+    }
+
+    pub fn list<T: AsRef<str>>(mut self, items: &[T]) -> Self {
+        let list = items
+            .iter()
+            .map(|item| format!("- {}", item.as_ref()))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        self.blocks.push(list);
+        self
+    }
+
+    pub fn code_block(mut self, language: &str, code: &str) -> Self {
+        // The fence must be longer than any run of backticks in the code to not be closed early
+        let longest_run = code
+            .split(|c| c != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or_default();
+        let fence = "`".repeat(longest_run.max(2) + 1);
+
+        self.blocks.push(format!(
+            "{}{}\n{}\n{}",
+            fence,
+            language,
+            code.trim_end(),
+            fence
+        ));
+        self
+    }
+
+    pub fn table<T: AsRef<str>>(mut self, headers: &[T], rows: &[Vec<String>]) -> Self {
+        let row = |cells: Vec<&str>| {
+            let cells: Vec<String> = cells.iter().map(|cell| escape_cell(cell)).collect();
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let mut table = vec![
+            row(headers.iter().map(AsRef::as_ref).collect()),
+            row(headers.iter().map(|_| "---").collect()),
+        ];
+
+        for cells in rows {
+            table.push(row(cells.iter().map(String::as_str).collect()));
+        }
+
+        self.blocks.push(table.join("\n"));
+        self
+    }
+
+    /// Wraps the content of the given document in a collapsible section
+    pub fn details(mut self, summary: &str, content: Markdown) -> Self {
+        self.blocks.push(format!(
+            "<details>\n<summary>{}</summary>\n\n{}\n\n</details>",
+            summary, content
+        ));
+        self
+    }
+
+    pub fn build(&self) -> String {
+        self.blocks.join("\n\n")
+    }
+}
+
+impl Display for Markdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.build())
+    }
+}
+
+impl From<Markdown> for String {
+    fn from(markdown: Markdown) -> Self {
+        markdown.build()
+    }
+}
+
+fn escape_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Markdown;
+
+    #[test]
+    fn build_separates_blocks_with_blank_line() {
+        let markdown = Markdown::new()
+            .heading(2, "Summary")
+            .paragraph("All checks passed.")
+            .list(&["first", "second"]);
+
+        assert_eq!(
+            "## Summary\n\nAll checks passed.\n\n- first\n- second",
+            markdown.build()
+        );
+    }
+
+    #[test]
+    fn table_escapes_pipes() {
+        let markdown =
+            Markdown::new().table(&["Name", "Result"], &[vec!["a|b".into(), "ok".into()]]);
+
+        assert_eq!(
+            "| Name | Result |\n| --- | --- |\n| a\\|b | ok |",
+            markdown.build()
+        );
+    }
+
+    #[test]
+    fn code_block_uses_longer_fence_for_nested_fences() {
+        let markdown = Markdown::new().code_block("md", "```rust\nfn main() {}\n```");
+
+        assert!(markdown.build().starts_with("````md\n"));
+    }
+
+    #[test]
+    fn code_block_uses_fence_longer_than_longest_backtick_run() {
+        let markdown = Markdown::new().code_block("md", "````\n```\n````");
+
+        assert_eq!("`````md\n````\n```\n````\n`````", markdown.build());
+    }
+}
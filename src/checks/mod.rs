@@ -0,0 +1,275 @@
+//! Helpers for GitHub's Checks API
+//!
+//! Apps that run CI-style workflows report their results as check runs. A [`CheckRun`] is
+//! created in the `queued` state, moves to `in_progress` when the work starts, and is `completed`
+//! with a [`Conclusion`] and an [`Output`]. The output's summary and text are Markdown, which can
+//! be built with [`Markdown`].
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::client::{GitHubClient, GitHubError};
+
+pub use self::markdown::Markdown;
+pub use self::request::CheckRequest;
+
+mod markdown;
+mod request;
+
+/// Maximum number of annotations that can be sent to GitHub in a single request
+pub const MAX_ANNOTATIONS_PER_REQUEST: usize = 50;
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Conclusion {
+    Success,
+    Failure,
+    Neutral,
+    Cancelled,
+    Skipped,
+    TimedOut,
+    ActionRequired,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Failure,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct Annotation {
+    path: String,
+    start_line: u64,
+    end_line: u64,
+    annotation_level: AnnotationLevel,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_details: Option<String>,
+}
+
+impl Annotation {
+    pub fn new(
+        path: &str,
+        start_line: u64,
+        end_line: u64,
+        level: AnnotationLevel,
+        message: &str,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            start_line,
+            end_line,
+            annotation_level: level,
+            message: message.into(),
+            title: None,
+            raw_details: None,
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn raw_details(mut self, raw_details: &str) -> Self {
+        self.raw_details = Some(raw_details.into());
+        self
+    }
+}
+
+/// Button that is shown on a check run
+///
+/// When a user clicks the button, GitHub sends a `check_run` event with the action
+/// `requested_action` and the button's identifier. See [`CheckRequest::ActionRequested`].
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct CheckRunAction {
+    label: String,
+    description: String,
+    identifier: String,
+}
+
+impl CheckRunAction {
+    pub fn new(label: &str, description: &str, identifier: &str) -> Self {
+        Self {
+            label: label.into(),
+            description: description.into(),
+            identifier: identifier.into(),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Output {
+    title: String,
+    summary: String,
+    text: Option<String>,
+    annotations: Vec<Annotation>,
+}
+
+impl Output {
+    pub fn new(title: &str, summary: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            summary: summary.into(),
+            text: None,
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    pub fn annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    pub fn annotations(mut self, annotations: Vec<Annotation>) -> Self {
+        self.annotations.extend(annotations);
+        self
+    }
+
+    fn to_json(&self, annotations: &[Annotation]) -> Value {
+        let mut output = json!({
+            "title": self.title,
+            "summary": self.summary,
+            "annotations": annotations,
+        });
+
+        if let Some(text) = &self.text {
+            output["text"] = Value::String(text.clone());
+        }
+
+        output
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRunResponse {
+    id: u64,
+}
+
+/// Check run on a commit
+///
+/// The client that is used to create or load the check run must authenticate as the installation
+/// that owns the repository.
+#[derive(Clone, Debug)]
+pub struct CheckRun {
+    github_client: GitHubClient,
+    repository: String,
+    id: u64,
+}
+
+impl CheckRun {
+    /// Creates a new check run in the `queued` state
+    pub async fn create(
+        github_client: &GitHubClient,
+        repository: &str,
+        name: &str,
+        head_sha: &str,
+        actions: &[CheckRunAction],
+    ) -> Result<Self, GitHubError> {
+        let response: CheckRunResponse = github_client
+            .post(
+                &format!("repos/{}/check-runs", repository),
+                &json!({
+                    "name": name,
+                    "head_sha": head_sha,
+                    "status": "queued",
+                    "actions": actions,
+                }),
+            )
+            .await?;
+
+        Ok(Self::from_id(github_client, repository, response.id))
+    }
+
+    /// Returns an existing check run, for example one that was rerequested
+    pub fn from_id(github_client: &GitHubClient, repository: &str, id: u64) -> Self {
+        Self {
+            github_client: github_client.clone(),
+            repository: repository.into(),
+            id,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub async fn start(&self) -> Result<(), GitHubError> {
+        self.update(json!({
+            "status": "in_progress",
+            "started_at": Utc::now().to_rfc3339(),
+        }))
+        .await
+    }
+
+    /// Adds the output and its annotations to the check run without changing its status
+    ///
+    /// GitHub accepts at most [`MAX_ANNOTATIONS_PER_REQUEST`] annotations per request, so the
+    /// annotations are sent in batches. Each batch adds to the annotations that are already on
+    /// the check run.
+    pub async fn annotate(&self, output: &Output) -> Result<(), GitHubError> {
+        if output.annotations.is_empty() {
+            return self.update(json!({ "output": output.to_json(&[]) })).await;
+        }
+
+        for batch in output.annotations.chunks(MAX_ANNOTATIONS_PER_REQUEST) {
+            self.update(json!({ "output": output.to_json(batch) }))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Completes the check run with a conclusion and its output
+    ///
+    /// Annotations that do not fit into the final request are sent beforehand.
+    pub async fn complete(
+        &self,
+        conclusion: Conclusion,
+        output: &Output,
+    ) -> Result<(), GitHubError> {
+        let (earlier, last) = match output.annotations.len() {
+            0 => (&[][..], &[][..]),
+            length => {
+                let split =
+                    (length - 1) / MAX_ANNOTATIONS_PER_REQUEST * MAX_ANNOTATIONS_PER_REQUEST;
+                output.annotations.split_at(split)
+            }
+        };
+
+        for batch in earlier.chunks(MAX_ANNOTATIONS_PER_REQUEST) {
+            self.update(json!({ "output": output.to_json(batch) }))
+                .await?;
+        }
+
+        self.update(json!({
+            "status": "completed",
+            "conclusion": conclusion,
+            "completed_at": Utc::now().to_rfc3339(),
+            "output": output.to_json(last),
+        }))
+        .await
+    }
+
+    async fn update(&self, body: Value) -> Result<(), GitHubError> {
+        self.github_client
+            .patch::<_, Value>(
+                &format!("repos/{}/check-runs/{}", self.repository, self.id),
+                &body,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use crate::Delivery;
+
+/// Request by a user to run checks again, or to perform an action of a check run
+///
+/// Users can re-run a single check run or a whole check suite from GitHub's interface, which sends
+/// a `check_run` or `check_suite` event with the action `rerequested`. Buttons that were added
+/// to a check run with [`CheckRunAction`](crate::checks::CheckRunAction) send a `check_run` event
+/// with the action `requested_action`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum CheckRequest {
+    CheckRunRerequested {
+        check_run: u64,
+        name: String,
+        head_sha: String,
+    },
+    CheckSuiteRerequested {
+        head_sha: String,
+    },
+    ActionRequested {
+        check_run: u64,
+        identifier: String,
+        head_sha: String,
+    },
+}
+
+impl CheckRequest {
+    pub fn from_delivery(delivery: &Delivery) -> Option<Self> {
+        let payload = &delivery.payload;
+
+        match (delivery.event.as_str(), delivery.action()?) {
+            ("check_run", "rerequested") => Some(CheckRequest::CheckRunRerequested {
+                check_run: payload["check_run"]["id"].as_u64()?,
+                name: payload["check_run"]["name"].as_str()?.into(),
+                head_sha: payload["check_run"]["head_sha"].as_str()?.into(),
+            }),
+            ("check_run", "requested_action") => Some(CheckRequest::ActionRequested {
+                check_run: payload["check_run"]["id"].as_u64()?,
+                identifier: payload["requested_action"]["identifier"].as_str()?.into(),
+                head_sha: payload["check_run"]["head_sha"].as_str()?.into(),
+            }),
+            ("check_suite", "rerequested") => Some(CheckRequest::CheckSuiteRerequested {
+                head_sha: payload["check_suite"]["head_sha"].as_str()?.into(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn head_sha(&self) -> &str {
+        match self {
+            CheckRequest::CheckRunRerequested { head_sha, .. } => head_sha,
+            CheckRequest::CheckSuiteRerequested { head_sha } => head_sha,
+            CheckRequest::ActionRequested { head_sha, .. } => head_sha,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Delivery;
+
+    use super::CheckRequest;
+
+    #[test]
+    fn from_delivery_returns_rerequested_check_run() {
+        let delivery = Delivery::new(
+            None,
+            "check_run",
+            json!({
+                "action": "rerequested",
+                "check_run": { "id": 4, "name": "octox", "head_sha": "ce587453" }
+            }),
+        );
+
+        assert_eq!(
+            Some(CheckRequest::CheckRunRerequested {
+                check_run: 4,
+                name: "octox".into(),
+                head_sha: "ce587453".into()
+            }),
+            CheckRequest::from_delivery(&delivery)
+        );
+    }
+
+    #[test]
+    fn from_delivery_returns_requested_action() {
+        let delivery = Delivery::new(
+            None,
+            "check_run",
+            json!({
+                "action": "requested_action",
+                "check_run": { "id": 4, "name": "octox", "head_sha": "ce587453" },
+                "requested_action": { "identifier": "fix" }
+            }),
+        );
+
+        let request = CheckRequest::from_delivery(&delivery).unwrap();
+
+        assert!(matches!(
+            request,
+            CheckRequest::ActionRequested { identifier, .. } if identifier == "fix"
+        ));
+    }
+
+    #[test]
+    fn from_delivery_ignores_other_actions() {
+        let delivery = Delivery::new(None, "check_run", json!({ "action": "created" }));
+
+        assert_eq!(None, CheckRequest::from_delivery(&delivery));
+    }
+}
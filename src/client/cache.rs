@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Map with a fixed capacity that evicts the least recently used entry when it is full
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<K, (u64, V)>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.clock += 1;
+        let clock = self.clock;
+
+        self.entries.get_mut(key).map(|(used, value)| {
+            *used = clock;
+            value.clone()
+        })
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(key, _)| key.clone());
+
+            if let Some(key) = least_recently_used {
+                self.entries.remove(&key);
+            }
+        }
+
+        self.clock += 1;
+        self.entries.insert(key, (self.clock, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruCache;
+
+    #[test]
+    fn insert_evicts_least_recently_used_entry() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.get(&"a");
+
+        cache.insert("c", 3);
+
+        assert_eq!(2, cache.entries.len());
+        assert_eq!(Some(1), cache.get(&"a"));
+        assert_eq!(None, cache.get(&"b"));
+        assert_eq!(Some(3), cache.get(&"c"));
+    }
+
+    #[test]
+    fn insert_replaces_existing_entry_without_eviction() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+
+        cache.insert("a", 3);
+
+        assert_eq!(Some(3), cache.get(&"a"));
+        assert_eq!(Some(2), cache.get(&"b"));
+    }
+
+    #[test]
+    fn insert_ignores_entries_without_capacity() {
+        let mut cache = LruCache::new(0);
+        cache.insert("a", 1);
+
+        assert_eq!(0, cache.entries.len());
+    }
+}
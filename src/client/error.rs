@@ -0,0 +1,43 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+use crate::client::graphql::GraphQLError;
+use crate::WorkflowError;
+
+#[derive(Debug, Error)]
+pub enum GitHubError {
+    #[error("failed to create token: {0}")]
+    Token(String),
+
+    #[error("GitHub responded with {status}: {message}")]
+    Status { status: StatusCode, message: String },
+
+    #[error("rate limit exceeded, resets in {0} seconds")]
+    RateLimited(u64),
+
+    #[error("GraphQL query failed: {}", format_graphql_errors(.0))]
+    GraphQL(Vec<GraphQLError>),
+
+    #[error("GraphQL variables must be an object, got {0}")]
+    InvalidVariables(String),
+
+    #[error("failed to deserialize response from GitHub")]
+    Deserialization(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+impl From<GitHubError> for WorkflowError {
+    fn from(error: GitHubError) -> Self {
+        WorkflowError::UnexpectedError(error.into())
+    }
+}
+
+fn format_graphql_errors(errors: &[GraphQLError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join("; ")
+}
@@ -0,0 +1,174 @@
+use std::fmt::{Display, Formatter};
+
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use github_parts::github::GitHubHost;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::client::{GitHubClient, GitHubError};
+
+/// Error returned by GitHub's GraphQL API
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub path: Vec<Value>,
+}
+
+impl Display for GraphQLError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            Some(kind) => write!(f, "{} ({})", self.message, kind),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse {
+    #[serde(default)]
+    data: Value,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+/// Page of a connection in GitHub's GraphQL API
+///
+/// Connections must select `nodes` and `pageInfo { hasNextPage endCursor }` to be paginated.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection<T> {
+    pub nodes: Vec<T>,
+    pub page_info: PageInfo,
+}
+
+/// Returns the endpoint of the GraphQL API for the given host
+///
+/// The REST API of GitHub Enterprise Server is served under `/api/v3`, while the GraphQL API is
+/// served under `/api/graphql`. On github.com, the GraphQL API is available at `/graphql`.
+pub(crate) fn endpoint(github_host: &GitHubHost) -> String {
+    let host = github_host.get().trim_end_matches('/');
+
+    match host.strip_suffix("/api/v3") {
+        Some(host) => format!("{}/api/graphql", host),
+        None => format!("{}/graphql", host),
+    }
+}
+
+impl GitHubClient {
+    /// Executes a query or mutation against GitHub's GraphQL API
+    ///
+    /// Errors in the response are returned as [`GitHubError::GraphQL`], even if GitHub returned
+    /// partial data.
+    pub async fn graphql<V: Serialize, T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: &V,
+    ) -> Result<T, GitHubError> {
+        let body = json!({
+            "query": query,
+            "variables": variables,
+        });
+
+        let response: GraphQLResponse = self
+            .request(Method::POST, &endpoint(self.github_host()), Some(&body))
+            .await?;
+
+        if !response.errors.is_empty() {
+            return Err(GitHubError::GraphQL(response.errors));
+        }
+
+        Ok(serde_json::from_value(response.data)?)
+    }
+
+    /// Returns a stream with the nodes of a connection on all pages
+    ///
+    /// The query must accept a `$cursor: String` variable and pass it as the `after` argument to
+    /// the connection. `path` points to the connection inside the response's data, for example
+    /// `&["repository", "pullRequest", "reviewThreads"]`. The variables must be an object or
+    /// `null`.
+    pub fn graphql_paginate<'a, T: DeserializeOwned + Send + 'static>(
+        &'a self,
+        query: &'a str,
+        variables: Value,
+        path: &'a [&'a str],
+    ) -> impl Stream<Item = Result<T, GitHubError>> + Send + 'a {
+        let variables = match variables {
+            Value::Null => Value::Object(Map::new()),
+            Value::Object(_) => variables,
+            // The cursor could not be added, so the first page would be requested forever
+            variables => {
+                let error = GitHubError::InvalidVariables(variables.to_string());
+                return stream::once(async move { Err(error) }).boxed();
+            }
+        };
+
+        stream::try_unfold(Some(Value::Null), move |cursor| {
+            let mut variables = variables.clone();
+
+            async move {
+                let cursor = match cursor {
+                    Some(cursor) => cursor,
+                    None => return Ok(None),
+                };
+
+                variables["cursor"] = cursor;
+
+                let mut data: Value = self.graphql(query, &variables).await?;
+                for key in path {
+                    data = data[*key].take();
+                }
+
+                let connection: Connection<T> = serde_json::from_value(data)?;
+
+                let next_cursor = match connection.page_info {
+                    PageInfo {
+                        has_next_page: true,
+                        end_cursor: Some(cursor),
+                    } => Some(Value::String(cursor)),
+                    _ => None,
+                };
+
+                Ok::<_, GitHubError>(Some((connection.nodes, next_cursor)))
+            }
+        })
+        .map_ok(|nodes| stream::iter(nodes.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use github_parts::github::GitHubHost;
+
+    use super::endpoint;
+
+    #[test]
+    fn endpoint_for_github_com() {
+        let github_host = GitHubHost::new("https://api.github.com".into());
+
+        assert_eq!("https://api.github.com/graphql", endpoint(&github_host));
+    }
+
+    #[test]
+    fn endpoint_for_github_enterprise_server() {
+        let github_host = GitHubHost::new("https://github.example.com/api/v3/".into());
+
+        assert_eq!(
+            "https://github.example.com/api/graphql",
+            endpoint(&github_host)
+        );
+    }
+}
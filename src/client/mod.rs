@@ -0,0 +1,463 @@
+//! Client for GitHub's REST API
+//!
+//! [`GitHubClient`] authenticates either as the app or as one of its installations, and takes care
+//! of the details of talking to GitHub: it follows `Link` headers to paginate, waits when a rate
+//! limit has been exceeded, retries idempotent requests that failed on GitHub's side, and makes
+//! conditional requests using `ETag`s so that unchanged resources do not count against the rate
+//! limit.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use github_parts::github::app::AppId;
+use github_parts::github::token::TokenFactory;
+use github_parts::github::{GitHubHost, PrivateKey};
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, HeaderValue, ETAG, IF_NONE_MATCH, LINK};
+use reqwest::{Client, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::SharedTokenFactory;
+
+use self::cache::LruCache;
+use self::pagination::next_page;
+use self::rate_limit::{backoff, Backoff};
+
+pub use self::error::GitHubError;
+pub use self::graphql::{Connection, GraphQLError, PageInfo};
+
+mod cache;
+mod error;
+mod graphql;
+mod pagination;
+mod rate_limit;
+
+/// Version of GitHub's REST API that the client requests
+pub const API_VERSION: &str = "v3";
+
+/// Number of responses that are cached for conditional requests
+const CACHE_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug)]
+struct InstallationToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessTokenResponse {
+    token: String,
+    expires_at: String,
+}
+
+#[derive(Clone, Debug)]
+struct CachedResponse {
+    etag: String,
+    link: Option<HeaderValue>,
+    body: Bytes,
+}
+
+#[derive(Debug)]
+struct Response {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+#[derive(Debug)]
+struct Inner {
+    github_host: GitHubHost,
+    token_factory: SharedTokenFactory,
+    http: Client,
+    installation_tokens: Mutex<HashMap<u64, InstallationToken>>,
+    cache: Mutex<LruCache<(Option<u64>, String), CachedResponse>>,
+}
+
+/// Client for GitHub's REST API
+///
+/// The client authenticates as the app by default. Use [`GitHubClient::installation`] to get a
+/// client that authenticates as an installation of the app. Clients are cheap to clone, and clones
+/// share their tokens and cached responses.
+#[derive(Clone, Debug)]
+pub struct GitHubClient {
+    inner: Arc<Inner>,
+    installation: Option<u64>,
+    max_retries: u32,
+    max_wait: Duration,
+}
+
+impl GitHubClient {
+    pub fn new(github_host: GitHubHost, app_id: AppId, private_key: PrivateKey) -> Self {
+        let token_factory = TokenFactory::new(github_host.clone(), app_id, private_key);
+
+        Self::with_token_factory(github_host, Arc::new(Mutex::new(token_factory)))
+    }
+
+    pub(crate) fn with_token_factory(
+        github_host: GitHubHost,
+        token_factory: SharedTokenFactory,
+    ) -> Self {
+        let inner = Inner {
+            github_host,
+            token_factory,
+            http: Client::new(),
+            installation_tokens: Mutex::new(HashMap::new()),
+            cache: Mutex::new(LruCache::new(CACHE_CAPACITY)),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+            installation: None,
+            max_retries: 3,
+            max_wait: Duration::from_secs(60),
+        }
+    }
+
+    /// Returns a client that authenticates as the given installation
+    pub fn installation(&self, installation: u64) -> Self {
+        Self {
+            installation: Some(installation),
+            ..self.clone()
+        }
+    }
+
+    /// Sets how often a request is retried after it hit a rate limit or failed on GitHub's side
+    ///
+    /// Only idempotent requests are retried after a server error, since GitHub might have applied
+    /// the change before it failed.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the longest time that the client waits before retrying a request
+    ///
+    /// Requests that would have to wait longer for a rate limit to reset fail with
+    /// [`GitHubError::RateLimited`]. Server errors whose `Retry-After` is longer are not retried.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    pub fn github_host(&self) -> &GitHubHost {
+        &self.inner.github_host
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, GitHubError> {
+        self.request::<(), T>(Method::GET, path, None).await
+    }
+
+    pub async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, GitHubError> {
+        self.request(Method::POST, path, Some(body)).await
+    }
+
+    pub async fn patch<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, GitHubError> {
+        self.request(Method::PATCH, path, Some(body)).await
+    }
+
+    pub async fn put<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, GitHubError> {
+        self.request(Method::PUT, path, Some(body)).await
+    }
+
+    pub async fn delete(&self, path: &str) -> Result<(), GitHubError> {
+        self.request::<(), ()>(Method::DELETE, path, None).await
+    }
+
+    /// Returns a stream with the items on all pages of a paginated endpoint
+    ///
+    /// The endpoint must return a JSON array. The next page is only requested once all items of
+    /// the current page have been consumed.
+    pub fn paginate<T: DeserializeOwned + Send + 'static>(
+        &self,
+        path: &str,
+    ) -> impl Stream<Item = Result<T, GitHubError>> + Send + '_ {
+        let first_page = Some(self.url(path));
+
+        stream::try_unfold(first_page, move |url| async move {
+            let url = match url {
+                Some(url) => url,
+                None => return Ok(None),
+            };
+
+            let response = self.send::<()>(Method::GET, &url, None).await?;
+            let items: Vec<T> = serde_json::from_slice(&response.body)?;
+
+            Ok::<_, GitHubError>(Some((items, next_page(&response.headers))))
+        })
+        .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed()
+    }
+
+    pub async fn request<B: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&B>,
+    ) -> Result<T, GitHubError> {
+        let url = self.url(path);
+        let response = self.send(method, &url, body).await?;
+
+        // Endpoints that respond with `204 No Content` can be deserialized as `()`
+        let body: &[u8] = if response.body.is_empty() {
+            b"null"
+        } else {
+            &response.body
+        };
+
+        Ok(serde_json::from_slice(body)?)
+    }
+
+    fn url(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            path.into()
+        } else {
+            format!(
+                "{}/{}",
+                self.inner.github_host.get().trim_end_matches('/'),
+                path.trim_start_matches('/')
+            )
+        }
+    }
+
+    async fn send<B: Serialize>(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&B>,
+    ) -> Result<Response, GitHubError> {
+        let token = self.token().await?;
+        self.send_with_token(method, url, body, &token).await
+    }
+
+    #[tracing::instrument(skip(self, body, token), fields(installation = ?self.installation))]
+    async fn send_with_token<B: Serialize>(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&B>,
+        token: &str,
+    ) -> Result<Response, GitHubError> {
+        let cache_key = (self.installation, url.to_string());
+        let cached = if method == Method::GET {
+            self.inner.cache.lock().get(&cache_key)
+        } else {
+            None
+        };
+
+        let mut attempt = 0;
+
+        loop {
+            let mut request = self
+                .inner
+                .http
+                .request(method.clone(), url)
+                .header("Authorization", format!("Bearer {}", token))
+                .header(
+                    "Accept",
+                    format!("application/vnd.github.{}+json", API_VERSION),
+                )
+                .header("User-Agent", "devxbots/octox");
+
+            if let Some(cached) = &cached {
+                request = request.header(IF_NONE_MATCH, &cached.etag);
+            }
+
+            #[cfg(feature = "otel")]
+            {
+                let mut headers = HeaderMap::new();
+                crate::telemetry::inject_context(&mut headers);
+                request = request.headers(headers);
+            }
+
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            #[cfg(feature = "metrics")]
+            let started_at = std::time::Instant::now();
+
+            let response = request.send().await;
+
+            #[cfg(feature = "metrics")]
+            crate::metrics::Metrics::global().record_github_request(
+                &method,
+                response
+                    .as_ref()
+                    .ok()
+                    .map(|response| (response.status(), response.headers())),
+                started_at.elapsed(),
+            );
+
+            let response = response?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().await?;
+
+            if status == StatusCode::NOT_MODIFIED {
+                if let Some(cached) = cached {
+                    // GitHub does not repeat the pagination links in the response to a conditional
+                    // request
+                    let mut headers = headers;
+                    if let Some(link) = cached.link {
+                        headers.insert(LINK, link);
+                    }
+
+                    return Ok(Response {
+                        headers,
+                        body: cached.body,
+                    });
+                }
+            }
+
+            if status.is_success() {
+                if method == Method::GET {
+                    if let Some(etag) = headers.get(ETAG).and_then(|etag| etag.to_str().ok()) {
+                        let cached = CachedResponse {
+                            etag: etag.into(),
+                            link: headers.get(LINK).cloned(),
+                            body: body.clone(),
+                        };
+                        self.inner.cache.lock().insert(cache_key, cached);
+                    }
+                }
+
+                return Ok(Response { headers, body });
+            }
+
+            let wait = match backoff(status, &headers, &body, attempt, Utc::now().timestamp()) {
+                Some(_) if attempt >= self.max_retries => None,
+                // Retrying a request that is not idempotent could e.g. create a check run twice
+                Some(Backoff::ServerError(_) | Backoff::Unavailable(_))
+                    if !is_idempotent(&method) =>
+                {
+                    None
+                }
+                // Retrying earlier than GitHub asked for would most likely fail again
+                Some(Backoff::Unavailable(wait)) if wait > self.max_wait => None,
+                Some(Backoff::Unavailable(wait)) => Some(wait),
+                Some(Backoff::RateLimit(wait)) if wait > self.max_wait => {
+                    return Err(GitHubError::RateLimited(wait.as_secs()));
+                }
+                Some(Backoff::RateLimit(wait)) => Some(wait),
+                Some(Backoff::ServerError(wait)) => Some(wait.min(self.max_wait)),
+                None => None,
+            };
+
+            match wait {
+                Some(wait) => {
+                    tracing::warn!(%status, ?wait, attempt, "retrying request to GitHub");
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                None => {
+                    return Err(GitHubError::Status {
+                        status,
+                        message: error_message(&body),
+                    })
+                }
+            }
+        }
+    }
+
+    async fn token(&self) -> Result<String, GitHubError> {
+        match self.installation {
+            Some(installation) => self.installation_token(installation).await,
+            None => self.app_token(),
+        }
+    }
+
+    /// Returns a JWT that authenticates as the app
+    pub fn app_token(&self) -> Result<String, GitHubError> {
+        self.inner
+            .token_factory
+            .lock()
+            .app()
+            .map(|token| token.get().to_string())
+            .map_err(|error| GitHubError::Token(error.to_string()))
+    }
+
+    /// Returns an access token for the installation, which is cached until shortly before it
+    /// expires
+    pub async fn installation_token(&self, installation: u64) -> Result<String, GitHubError> {
+        // Installation tokens are valid for one hour, but are renewed a minute before they expire
+        // to account for clock drift and requests that are in flight
+        let valid_until = Utc::now() + chrono::Duration::minutes(1);
+
+        if let Some(token) = self.inner.installation_tokens.lock().get(&installation) {
+            if token.expires_at > valid_until {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let token = self.create_installation_token(installation).await;
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::Metrics::global().record_token_refresh(token.is_ok());
+
+        let token = token?;
+        self.inner
+            .installation_tokens
+            .lock()
+            .insert(installation, token.clone());
+
+        Ok(token.token)
+    }
+
+    async fn create_installation_token(
+        &self,
+        installation: u64,
+    ) -> Result<InstallationToken, GitHubError> {
+        let url = self.url(&format!("app/installations/{}/access_tokens", installation));
+        let app_token = self.app_token()?;
+
+        let response = self
+            .send_with_token::<()>(Method::POST, &url, None, &app_token)
+            .await?;
+        let access_token: AccessTokenResponse = serde_json::from_slice(&response.body)?;
+
+        let expires_at = DateTime::parse_from_rfc3339(&access_token.expires_at)
+            .map(|expires_at| expires_at.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        Ok(InstallationToken {
+            token: access_token.token,
+            expires_at,
+        })
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+fn error_message(body: &[u8]) -> String {
+    #[derive(Deserialize)]
+    struct ErrorResponse {
+        message: String,
+    }
+
+    match serde_json::from_slice::<ErrorResponse>(body) {
+        Ok(error) => error.message,
+        Err(_) => String::from_utf8_lossy(body).into(),
+    }
+}
@@ -0,0 +1,67 @@
+use reqwest::header::{HeaderMap, LINK};
+
+/// Returns the URL of the next page from the `Link` header
+///
+/// GitHub paginates large responses and links to the next page in the `Link` header, for example
+/// `<https://api.github.com/repositories/1/issues?page=2>; rel="next"`.
+pub fn next_page(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+
+    link.split(',').find_map(|part| {
+        let mut segments = part.split(';');
+
+        let url = segments.next()?.trim();
+        let is_next = segments.any(|segment| segment.trim() == "rel=\"next\"");
+
+        if is_next {
+            url.strip_prefix('<')
+                .and_then(|url| url.strip_suffix('>'))
+                .map(String::from)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{HeaderMap, HeaderValue, LINK};
+
+    use super::next_page;
+
+    #[test]
+    fn next_page_returns_url_of_next_page() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            LINK,
+            HeaderValue::from_static(
+                "<https://api.github.com/repos/o/r/issues?page=1>; rel=\"prev\", \
+                 <https://api.github.com/repos/o/r/issues?page=3>; rel=\"next\", \
+                 <https://api.github.com/repos/o/r/issues?page=5>; rel=\"last\"",
+            ),
+        );
+
+        assert_eq!(
+            Some("https://api.github.com/repos/o/r/issues?page=3".into()),
+            next_page(&headers)
+        );
+    }
+
+    #[test]
+    fn next_page_returns_none_on_last_page() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            LINK,
+            HeaderValue::from_static(
+                "<https://api.github.com/repos/o/r/issues?page=1>; rel=\"prev\"",
+            ),
+        );
+
+        assert_eq!(None, next_page(&headers));
+    }
+
+    #[test]
+    fn next_page_returns_none_without_header() {
+        assert_eq!(None, next_page(&HeaderMap::new()));
+    }
+}
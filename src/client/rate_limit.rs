@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+
+/// Time to wait before a failed request can be retried
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Backoff {
+    /// The primary or secondary rate limit has been exceeded
+    RateLimit(Duration),
+    /// GitHub failed to process the request
+    ServerError(Duration),
+    /// GitHub failed to process the request, and asked to retry after a delay
+    Unavailable(Duration),
+}
+
+/// Returns how long to wait before retrying a request, or `None` if it should not be retried
+///
+/// GitHub signals that the primary rate limit has been exceeded by setting the
+/// `x-ratelimit-remaining` header to zero, and the time when it resets in `x-ratelimit-reset`.
+/// Secondary rate limits either set the `Retry-After` header, or require clients to wait at least
+/// one minute before retrying. Server errors are retried after the delay in `Retry-After`, or with
+/// an exponential backoff if the header is missing.
+pub fn backoff(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &[u8],
+    attempt: u32,
+    now: i64,
+) -> Option<Backoff> {
+    if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+        if let Some(seconds) = header_as_number(headers, RETRY_AFTER.as_str()) {
+            return Some(Backoff::RateLimit(Duration::from_secs(seconds)));
+        }
+
+        if header_as_number(headers, "x-ratelimit-remaining") == Some(0) {
+            let reset = header_as_number(headers, "x-ratelimit-reset").unwrap_or(0) as i64;
+            let seconds = (reset - now).max(0) as u64 + 1;
+
+            return Some(Backoff::RateLimit(Duration::from_secs(seconds)));
+        }
+
+        let is_secondary_rate_limit = String::from_utf8_lossy(body)
+            .to_lowercase()
+            .contains("secondary rate limit");
+
+        if status == StatusCode::TOO_MANY_REQUESTS || is_secondary_rate_limit {
+            return Some(Backoff::RateLimit(Duration::from_secs(60)));
+        }
+
+        return None;
+    }
+
+    match status {
+        StatusCode::INTERNAL_SERVER_ERROR
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::GATEWAY_TIMEOUT => match header_as_number(headers, RETRY_AFTER.as_str()) {
+            Some(seconds) => Some(Backoff::Unavailable(Duration::from_secs(seconds))),
+            None => Some(Backoff::ServerError(Duration::from_secs(
+                2u64.saturating_pow(attempt),
+            ))),
+        },
+        _ => None,
+    }
+}
+
+fn header_as_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use reqwest::StatusCode;
+
+    use super::{backoff, Backoff};
+
+    #[test]
+    fn backoff_waits_for_primary_rate_limit_to_reset() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("1060"));
+
+        let backoff = backoff(StatusCode::FORBIDDEN, &headers, b"", 0, 1000);
+
+        assert_eq!(Some(Backoff::RateLimit(Duration::from_secs(61))), backoff);
+    }
+
+    #[test]
+    fn backoff_respects_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+
+        let backoff = backoff(StatusCode::FORBIDDEN, &headers, b"", 0, 1000);
+
+        assert_eq!(Some(Backoff::RateLimit(Duration::from_secs(30))), backoff);
+    }
+
+    #[test]
+    fn backoff_waits_one_minute_for_secondary_rate_limit() {
+        let body = br#"{"message": "You have exceeded a secondary rate limit."}"#;
+
+        let backoff = backoff(StatusCode::FORBIDDEN, &HeaderMap::new(), body, 0, 1000);
+
+        assert_eq!(Some(Backoff::RateLimit(Duration::from_secs(60))), backoff);
+    }
+
+    #[test]
+    fn backoff_does_not_retry_forbidden_requests() {
+        let body = br#"{"message": "Resource not accessible by integration"}"#;
+
+        assert_eq!(
+            None,
+            backoff(StatusCode::FORBIDDEN, &HeaderMap::new(), body, 0, 1000)
+        );
+    }
+
+    #[test]
+    fn backoff_increases_exponentially_for_server_errors() {
+        let backoff = backoff(StatusCode::BAD_GATEWAY, &HeaderMap::new(), b"", 2, 1000);
+
+        assert_eq!(Some(Backoff::ServerError(Duration::from_secs(4))), backoff);
+    }
+
+    #[test]
+    fn backoff_respects_retry_after_for_server_errors() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+
+        let backoff = backoff(StatusCode::SERVICE_UNAVAILABLE, &headers, b"", 0, 1000);
+
+        assert_eq!(
+            Some(Backoff::Unavailable(Duration::from_secs(120))),
+            backoff
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+use crate::WorkflowConstructor;
+
+/// Role of a user in a repository
+///
+/// The variants are ordered from the lowest to the highest role, so that a role can be compared
+/// with the role that a command requires.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Permission {
+    None,
+    Read,
+    Triage,
+    Write,
+    Maintain,
+    Admin,
+}
+
+impl Permission {
+    pub(crate) fn from_role_name(role_name: &str) -> Option<Self> {
+        let permission = match role_name {
+            "admin" => Permission::Admin,
+            "maintain" => Permission::Maintain,
+            "write" => Permission::Write,
+            "triage" => Permission::Triage,
+            "read" => Permission::Read,
+            "none" => Permission::None,
+            _ => return None,
+        };
+
+        Some(permission)
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Permission::None => "none",
+            Permission::Read => "read",
+            Permission::Triage => "triage",
+            Permission::Write => "write",
+            Permission::Maintain => "maintain",
+            Permission::Admin => "admin",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Arity {
+    Required,
+    Optional,
+    Variadic,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Argument {
+    name: String,
+    arity: Arity,
+}
+
+impl Argument {
+    pub fn required(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            arity: Arity::Required,
+        }
+    }
+
+    pub fn optional(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            arity: Arity::Optional,
+        }
+    }
+
+    /// Argument that collects all remaining values
+    ///
+    /// A variadic argument must be the last argument of a command, and requires at least one
+    /// value.
+    pub fn variadic(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            arity: Arity::Variadic,
+        }
+    }
+}
+
+impl Display for Argument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.arity {
+            Arity::Required => write!(f, "<{}>", self.name),
+            Arity::Optional => write!(f, "[{}]", self.name),
+            Arity::Variadic => write!(f, "<{}...>", self.name),
+        }
+    }
+}
+
+/// Arguments that were passed to a command
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Arguments {
+    values: HashMap<String, Vec<String>>,
+}
+
+impl Arguments {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(name)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.values.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Error)]
+pub enum UsageError {
+    #[error("missing argument {0}")]
+    MissingArgument(String),
+    #[error("unexpected argument {0}")]
+    UnexpectedArgument(String),
+}
+
+/// Slash command that dispatches to its own workflow
+///
+/// Commands are declared with their name, arguments, and the permission that a user needs in the
+/// repository to run them. They are registered with [`Commands`](crate::command::Commands),
+/// which parses comments and executes the command's workflow.
+#[derive(Clone, Debug)]
+pub struct Command {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) arguments: Vec<Argument>,
+    pub(crate) permission: Permission,
+    pub(crate) workflow: WorkflowConstructor,
+}
+
+impl Command {
+    pub fn new(name: &str, workflow: WorkflowConstructor) -> Self {
+        Self {
+            name: name.to_lowercase(),
+            description: None,
+            arguments: Vec::new(),
+            permission: Permission::Write,
+            workflow,
+        }
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn argument(mut self, argument: Argument) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+
+    pub fn permission(mut self, permission: Permission) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    pub fn usage(&self, prefix: &str) -> String {
+        let mut usage = format!("{}{}", prefix, self.name);
+
+        for argument in &self.arguments {
+            usage.push_str(&format!(" {}", argument));
+        }
+
+        usage
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let mut seen_optional = false;
+
+        for (index, argument) in self.arguments.iter().enumerate() {
+            match argument.arity {
+                Arity::Required if seen_optional => {
+                    return Err(format!(
+                        "required argument {} of command {} follows an optional argument",
+                        argument.name, self.name
+                    ))
+                }
+                Arity::Optional => seen_optional = true,
+                Arity::Variadic if index != self.arguments.len() - 1 => {
+                    return Err(format!(
+                        "variadic argument {} of command {} must be the last argument",
+                        argument.name, self.name
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn parse_arguments(&self, values: &[String]) -> Result<Arguments, UsageError> {
+        let mut arguments = Arguments::default();
+        let mut values = values.iter();
+
+        for argument in &self.arguments {
+            let parsed: Vec<String> = match argument.arity {
+                Arity::Required => values.next().cloned().into_iter().collect(),
+                Arity::Optional => values.next().cloned().into_iter().collect(),
+                Arity::Variadic => values.by_ref().cloned().collect(),
+            };
+
+            if parsed.is_empty() {
+                if argument.arity == Arity::Optional {
+                    continue;
+                }
+
+                return Err(UsageError::MissingArgument(argument.to_string()));
+            }
+
+            arguments.values.insert(argument.name.clone(), parsed);
+        }
+
+        if let Some(unexpected) = values.next() {
+            return Err(UsageError::UnexpectedArgument(unexpected.clone()));
+        }
+
+        Ok(arguments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use github_parts::github::app::AppId;
+    use github_parts::github::{GitHubHost, PrivateKey};
+
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    use crate::{State, Step, Transition, Workflow, WorkflowError};
+
+    use super::{Argument, Command, Permission, UsageError};
+
+    #[derive(Debug)]
+    struct Noop;
+
+    impl Workflow for Noop {
+        fn initial_step(&self) -> Box<dyn Step> {
+            Box::new(NoopStep)
+        }
+    }
+
+    struct NoopStep;
+
+    #[async_trait]
+    impl Step for NoopStep {
+        async fn next(self: Box<Self>, _state: &mut State) -> Result<Transition, WorkflowError> {
+            Ok(Transition::Complete(json!(null)))
+        }
+    }
+
+    fn constructor(_: GitHubHost, _: AppId, _: PrivateKey) -> Box<dyn Workflow> {
+        Box::new(Noop)
+    }
+
+    fn values(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn permission_is_ordered_by_role() {
+        assert!(Permission::Admin > Permission::Write);
+        assert!(Permission::Triage < Permission::Write);
+    }
+
+    #[test]
+    fn usage_lists_arguments() {
+        let command = Command::new("label", constructor)
+            .argument(Argument::required("name"))
+            .argument(Argument::optional("color"));
+
+        assert_eq!("/label <name> [color]", command.usage("/"));
+    }
+
+    #[test]
+    fn parse_arguments_returns_named_values() {
+        let command = Command::new("assign", constructor).argument(Argument::variadic("users"));
+
+        let arguments = command
+            .parse_arguments(&values(&["@me", "@octocat"]))
+            .unwrap();
+
+        assert_eq!(Some("@me"), arguments.get("users"));
+        assert_eq!(2, arguments.get_all("users").len());
+    }
+
+    #[test]
+    fn parse_arguments_skips_missing_optional_argument() {
+        let command = Command::new("retest", constructor).argument(Argument::optional("check"));
+
+        let arguments = command.parse_arguments(&[]).unwrap();
+
+        assert_eq!(None, arguments.get("check"));
+    }
+
+    #[test]
+    fn parse_arguments_fails_for_missing_argument() {
+        let command = Command::new("label", constructor).argument(Argument::required("name"));
+
+        let error = command.parse_arguments(&[]).unwrap_err();
+
+        assert_eq!(UsageError::MissingArgument("<name>".into()), error);
+    }
+
+    #[test]
+    fn parse_arguments_fails_for_unexpected_argument() {
+        let command = Command::new("retest", constructor);
+
+        let error = command.parse_arguments(&values(&["now"])).unwrap_err();
+
+        assert_eq!(UsageError::UnexpectedArgument("now".into()), error);
+    }
+
+    #[test]
+    fn validate_rejects_variadic_argument_before_other_arguments() {
+        let command = Command::new("assign", constructor)
+            .argument(Argument::variadic("users"))
+            .argument(Argument::required("reason"));
+
+        assert!(command.validate().is_err());
+    }
+}
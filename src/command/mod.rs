@@ -0,0 +1,356 @@
+//! Slash commands in issue and pull request comments
+//!
+//! [`Commands`] is a [`Workflow`] that parses commands like `/retest` or `/label bug` from newly
+//! created comments, checks that the commenter has the permission to run them, and dispatches
+//! each command to its own workflow. The workflow finds the [`Invocation`] in its state, next to
+//! the original [`Event`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use github_parts::event::Event;
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::client::GitHubClient;
+use crate::{Delivery, Error, State, Step, Transition, Workflow, WorkflowError};
+
+use self::parser::{parse, CommandLine};
+use self::payload::IssueCommentPayload;
+
+pub use self::definition::{Argument, Arguments, Command, Permission, UsageError};
+
+mod definition;
+mod parser;
+mod payload;
+
+/// Command that was invoked in a comment
+#[derive(Clone, Debug)]
+pub struct Invocation {
+    pub name: String,
+    pub arguments: Arguments,
+    pub author: String,
+    pub permission: Permission,
+    pub repository: String,
+    pub issue: u64,
+    pub comment: u64,
+    pub installation: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CollaboratorPermission {
+    permission: String,
+    role_name: Option<String>,
+}
+
+#[derive(Debug)]
+struct Registry {
+    prefix: String,
+    commands: Vec<Command>,
+    workflows: HashMap<String, Box<dyn Workflow>>,
+    github_client: GitHubClient,
+}
+
+/// Workflow that dispatches slash commands to their workflows
+#[derive(Debug)]
+pub struct Commands {
+    github_host: GitHubHost,
+    app_id: AppId,
+    private_key: PrivateKey,
+    registry: Arc<Registry>,
+}
+
+impl Commands {
+    pub fn new(github_host: GitHubHost, app_id: AppId, private_key: PrivateKey) -> Self {
+        let github_client = GitHubClient::new(github_host.clone(), app_id, private_key.clone());
+
+        let registry = Registry {
+            prefix: "/".into(),
+            commands: Vec::new(),
+            workflows: HashMap::new(),
+            github_client,
+        };
+
+        Self {
+            github_host,
+            app_id,
+            private_key,
+            registry: Arc::new(registry),
+        }
+    }
+
+    pub fn prefix(mut self, prefix: &str) -> Result<Self, Error> {
+        if prefix.is_empty() || prefix.contains(char::is_whitespace) {
+            return Err(Error::Configuration(
+                "command prefix must not be empty or contain whitespace".into(),
+            ));
+        }
+
+        self.registry_mut()?.prefix = prefix.into();
+        Ok(self)
+    }
+
+    pub fn command(mut self, command: Command) -> Result<Self, Error> {
+        command.validate().map_err(Error::Configuration)?;
+
+        let workflow = (command.workflow)(
+            self.github_host.clone(),
+            self.app_id,
+            self.private_key.clone(),
+        );
+
+        let registry = self.registry_mut()?;
+
+        if command.name == "help" || registry.workflows.contains_key(&command.name) {
+            return Err(Error::Configuration(format!(
+                "command {} is already registered",
+                command.name
+            )));
+        }
+
+        registry.workflows.insert(command.name.clone(), workflow);
+        registry.commands.push(command);
+
+        Ok(self)
+    }
+
+    fn registry_mut(&mut self) -> Result<&mut Registry, Error> {
+        Arc::get_mut(&mut self.registry).ok_or_else(|| {
+            Error::Configuration("commands cannot be changed while they are running".into())
+        })
+    }
+}
+
+#[async_trait]
+impl Workflow for Commands {
+    fn initial_step(&self) -> Box<dyn Step> {
+        Box::new(Dispatch {
+            registry: self.registry.clone(),
+        })
+    }
+}
+
+struct Dispatch {
+    registry: Arc<Registry>,
+}
+
+/// Comment in which commands were invoked
+struct Comment {
+    payload: IssueCommentPayload,
+    raw_payload: Value,
+    delivery: Option<Delivery>,
+    installation: u64,
+    permission: Permission,
+}
+
+#[async_trait]
+impl Step for Dispatch {
+    async fn next(self: Box<Self>, state: &mut State) -> Result<Transition, WorkflowError> {
+        let event: &Event = state.get().context("failed to get event from state")?;
+
+        let raw_payload = match event {
+            Event::Unsupported(payload) => payload.clone(),
+            _ => return Ok(Transition::Complete("ignored event".into())),
+        };
+
+        // Other events, e.g. `discussion_comment`, have payloads with a comment body as well.
+        // Workflows that are executed without a delivery only get the event, and are trusted to
+        // pass an `issue_comment` payload.
+        if let Some(delivery) = state.get::<Delivery>() {
+            if delivery.event != "issue_comment" {
+                return Ok(Transition::Complete("ignored event".into()));
+            }
+        }
+
+        let payload: IssueCommentPayload = match serde_json::from_value(raw_payload.clone()) {
+            Ok(payload) => payload,
+            Err(_) => return Ok(Transition::Complete("ignored event".into())),
+        };
+
+        // Ignore edited comments to avoid running a command twice, and comments by bots to avoid
+        // loops between apps that reply to each other
+        if payload.action != "created" || payload.comment.user.kind == "Bot" {
+            return Ok(Transition::Complete("ignored comment".into()));
+        }
+
+        // Ordinary comments often contain lines like `/shrug` that are meant for other tools, so
+        // only registered commands are considered
+        let command_lines: Vec<CommandLine> = parse(&self.registry.prefix, &payload.comment.body)
+            .into_iter()
+            .filter(|command_line| self.registry.is_registered(&command_line.name))
+            .collect();
+        if command_lines.is_empty() {
+            return Ok(Transition::Complete("no commands in comment".into()));
+        }
+
+        let installation = payload
+            .installation
+            .as_ref()
+            .map(|installation| installation.id)
+            .ok_or_else(|| WorkflowError::MissingData("missing installation in payload".into()))?;
+
+        let github_client = self.registry.github_client.installation(installation);
+
+        let permission: CollaboratorPermission = github_client
+            .get(&format!(
+                "repos/{}/collaborators/{}/permission",
+                payload.repository.full_name, payload.comment.user.login
+            ))
+            .await?;
+
+        // Custom repository roles have their own name, but inherit one of the base permissions
+        let permission = permission
+            .role_name
+            .as_deref()
+            .and_then(Permission::from_role_name)
+            .or_else(|| Permission::from_role_name(&permission.permission))
+            .unwrap_or(Permission::None);
+
+        let comment = Comment {
+            payload,
+            raw_payload,
+            delivery: state.get::<Delivery>().cloned(),
+            installation,
+            permission,
+        };
+
+        let mut results = Vec::new();
+        let mut replies = Vec::new();
+
+        // A failing command must not prevent the replies to the other commands in the comment
+        for command_line in command_lines {
+            let name = command_line.name.clone();
+
+            let result = match self
+                .registry
+                .run(command_line, &comment, &mut replies)
+                .await
+            {
+                Ok(result) => result,
+                Err(error) => {
+                    tracing::error!(command = %name, %error, "failed to run command");
+
+                    replies.push(format!("Failed to run `{}{}`.", self.registry.prefix, name));
+                    json!({ "command": name, "status": "failed", "error": error.code() })
+                }
+            };
+
+            results.push(result);
+        }
+
+        let payload = comment.payload;
+
+        if !replies.is_empty() {
+            let body = format!(
+                "@{}\n\n{}",
+                payload.comment.user.login,
+                replies.join("\n\n")
+            );
+
+            github_client
+                .post::<_, Value>(
+                    &format!(
+                        "repos/{}/issues/{}/comments",
+                        payload.repository.full_name, payload.issue.number
+                    ),
+                    &json!({ "body": body }),
+                )
+                .await?;
+        }
+
+        Ok(Transition::Complete(json!({ "commands": results })))
+    }
+}
+
+impl Registry {
+    async fn run(
+        &self,
+        command_line: CommandLine,
+        comment: &Comment,
+        replies: &mut Vec<String>,
+    ) -> Result<Value, WorkflowError> {
+        let payload = &comment.payload;
+        let name = command_line.name.clone();
+
+        if name == "help" {
+            replies.push(self.help());
+            return Ok(json!({ "command": name, "status": "help" }));
+        }
+
+        let command = self
+            .commands
+            .iter()
+            .find(|command| command.name == name)
+            .context("failed to find command")?;
+
+        if comment.permission < command.permission {
+            replies.push(format!(
+                "You need the {} permission to run `{}{}`.",
+                command.permission, self.prefix, name
+            ));
+            return Ok(json!({ "command": name, "status": "forbidden" }));
+        }
+
+        let arguments = match command.parse_arguments(&command_line.arguments) {
+            Ok(arguments) => arguments,
+            Err(error) => {
+                replies.push(format!(
+                    "Failed to run `{}{}`: {}.\n\nUsage: `{}`",
+                    self.prefix,
+                    name,
+                    error,
+                    command.usage(&self.prefix)
+                ));
+                return Ok(json!({ "command": name, "status": "usage" }));
+            }
+        };
+
+        let workflow = self
+            .workflows
+            .get(&name)
+            .context("failed to find workflow for command")?;
+
+        let mut state = workflow.initial_state();
+        state.insert(Event::Unsupported(comment.raw_payload.clone()));
+        state.insert(Invocation {
+            name: name.clone(),
+            arguments,
+            author: payload.comment.user.login.clone(),
+            permission: comment.permission,
+            repository: payload.repository.full_name.clone(),
+            issue: payload.issue.number,
+            comment: payload.comment.id,
+            installation: comment.installation,
+        });
+
+        if let Some(delivery) = &comment.delivery {
+            state.insert(delivery.clone());
+        }
+
+        let result = workflow.run(state).await?;
+
+        Ok(json!({ "command": name, "status": "completed", "result": result }))
+    }
+
+    fn is_registered(&self, name: &str) -> bool {
+        name == "help" || self.workflows.contains_key(name)
+    }
+
+    fn help(&self) -> String {
+        let mut help = String::from("Available commands:\n");
+
+        for command in &self.commands {
+            help.push_str(&format!("\n- `{}`", command.usage(&self.prefix)));
+
+            if let Some(description) = &command.description {
+                help.push_str(&format!(": {}", description));
+            }
+        }
+
+        help
+    }
+}
@@ -0,0 +1,148 @@
+/// Command line found in a comment
+///
+/// A comment can contain multiple commands, one per line. Each command starts with the prefix
+/// (e.g. `/`), followed by its name and an optional list of whitespace-separated arguments.
+/// Arguments that contain whitespace can be wrapped in double quotes.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct CommandLine {
+    pub name: String,
+    pub arguments: Vec<String>,
+}
+
+pub fn parse(prefix: &str, body: &str) -> Vec<CommandLine> {
+    let mut in_code_block = false;
+    let mut commands = Vec::new();
+
+    for line in body.lines() {
+        let line = line.trim();
+
+        if line.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        if in_code_block || line.starts_with('>') {
+            continue;
+        }
+
+        if let Some(command) = parse_line(prefix, line) {
+            commands.push(command);
+        }
+    }
+
+    commands
+}
+
+fn parse_line(prefix: &str, line: &str) -> Option<CommandLine> {
+    let line = line.strip_prefix(prefix)?;
+
+    let mut tokens = tokenize(line).into_iter();
+    let name = tokens.next()?;
+
+    let is_valid_name = name
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_');
+
+    if !is_valid_name {
+        return None;
+    }
+
+    Some(CommandLine {
+        name: name.to_lowercase(),
+        arguments: tokens.collect(),
+    })
+}
+
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+
+    for char in line.chars() {
+        match char {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            char if char.is_whitespace() && !in_quotes => {
+                if has_token {
+                    tokens.push(std::mem::take(&mut token));
+                    has_token = false;
+                }
+            }
+            char => {
+                token.push(char);
+                has_token = true;
+            }
+        }
+    }
+
+    if has_token {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, CommandLine};
+
+    #[test]
+    fn parse_returns_command_without_arguments() {
+        let commands = parse("/", "/retest");
+
+        assert_eq!(
+            vec![CommandLine {
+                name: "retest".into(),
+                arguments: Vec::new()
+            }],
+            commands
+        );
+    }
+
+    #[test]
+    fn parse_returns_command_with_arguments() {
+        let commands = parse("/", "/assign @me @octocat");
+
+        assert_eq!(vec!["@me", "@octocat"], commands[0].arguments);
+    }
+
+    #[test]
+    fn parse_keeps_quoted_arguments_together() {
+        let commands = parse("/", "/label \"good first issue\" bug");
+
+        assert_eq!(vec!["good first issue", "bug"], commands[0].arguments);
+    }
+
+    #[test]
+    fn parse_returns_one_command_per_line() {
+        let commands = parse("/", "Looks good!\n\n/label bug\n  /retest\n");
+
+        let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["label", "retest"], names);
+    }
+
+    #[test]
+    fn parse_ignores_code_blocks_and_quotes() {
+        let commands = parse("/", "```\n/retest\n```\n> /label bug");
+
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn parse_ignores_paths() {
+        let commands = parse("/", "/usr/local/bin is missing");
+
+        assert!(commands.is_empty());
+    }
+
+    #[test]
+    fn parse_supports_custom_prefix() {
+        let commands = parse("!", "!retest\n/label bug");
+
+        assert_eq!("retest", commands[0].name);
+        assert_eq!(1, commands.len());
+    }
+}
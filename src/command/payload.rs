@@ -0,0 +1,44 @@
+use serde::Deserialize;
+
+/// Subset of the `issue_comment` webhook payload
+///
+/// `github-parts` does not deserialize `issue_comment` events yet, which is why they arrive as
+/// [`Event::Unsupported`](github_parts::event::Event::Unsupported). Only the fields that the
+/// command framework needs are deserialized here.
+#[derive(Clone, Debug, Deserialize)]
+pub struct IssueCommentPayload {
+    pub action: String,
+    pub comment: Comment,
+    pub issue: Issue,
+    pub repository: Repository,
+    pub installation: Option<Installation>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Comment {
+    pub id: u64,
+    pub body: String,
+    pub user: User,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Repository {
+    pub full_name: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Installation {
+    pub id: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct User {
+    pub login: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
@@ -0,0 +1,875 @@
+use std::fmt::{Display, Formatter};
+use std::fs::read_to_string;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey, WebhookSecret};
+use jsonwebtoken::EncodingKey;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::Error;
+
+const DEFAULT_GITHUB_HOST: &str = "https://api.github.com";
+const DEFAULT_SOCKET_ADDRESS: &str = "127.0.0.1:3000";
+const DEFAULT_WEBHOOK_PATH: &str = "/";
+const DEFAULT_HEALTH_PATH: &str = "/health";
+const DEFAULT_LIVENESS_PATH: &str = "/livez";
+const DEFAULT_READINESS_PATH: &str = "/readyz";
+const DEFAULT_METRICS_PATH: &str = "/metrics";
+const DEFAULT_READINESS_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Configuration of an octox app
+///
+/// The configuration can be loaded from a TOML or YAML file, and from environment variables. All
+/// values are optional when they are loaded, and are only checked when the configuration is
+/// validated. This makes it possible to combine multiple sources with [`OctoxConfig::merge`] and
+/// to report all problems with the final configuration at once.
+///
+/// | Field                  | Environment variable         |
+/// | ---------------------- | ---------------------------- |
+/// | `github_host`          | `OCTOX_GITHUB_HOST`          |
+/// | `app_id`               | `OCTOX_APP_ID`               |
+/// | `private_key`          | `OCTOX_PRIVATE_KEY`          |
+/// | `private_key_path`     | `OCTOX_PRIVATE_KEY_PATH`     |
+/// | `webhook_secret`       | `OCTOX_WEBHOOK_SECRET`       |
+/// | `webhook_secret_path`  | `OCTOX_WEBHOOK_SECRET_PATH`  |
+/// | `socket_address`       | `OCTOX_SOCKET_ADDRESS`       |
+/// | `path_prefix`          | `OCTOX_PATH_PREFIX`          |
+/// | `webhook_path`         | `OCTOX_WEBHOOK_PATH`         |
+/// | `health_path`          | `OCTOX_HEALTH_PATH`          |
+/// | `liveness_path`        | `OCTOX_LIVENESS_PATH`        |
+/// | `readiness_path`       | `OCTOX_READINESS_PATH`       |
+/// | `readiness_cache_ttl`  | `OCTOX_READINESS_CACHE_TTL`  |
+/// | `diagnostics_path`     | `OCTOX_DIAGNOSTICS_PATH`     |
+/// | `metrics_path`         | `OCTOX_METRICS_PATH`         |
+/// | `environment`          | `OCTOX_ENVIRONMENT`          |
+/// | `tls_certificate_path` | `OCTOX_TLS_CERTIFICATE_PATH` |
+/// | `tls_key_path`         | `OCTOX_TLS_KEY_PATH`         |
+/// | `tls_redirect_address` | `OCTOX_TLS_REDIRECT_ADDRESS` |
+/// | `unix_socket_path`     | `OCTOX_UNIX_SOCKET_PATH`     |
+/// | `unix_socket_mode`     | `OCTOX_UNIX_SOCKET_MODE`     |
+///
+/// The private key and webhook secret files are watched while the server is running, and are
+/// reloaded when they change.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OctoxConfig {
+    pub github_host: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub app_id: Option<String>,
+    pub private_key: Option<String>,
+    pub private_key_path: Option<PathBuf>,
+    pub webhook_secret: Option<String>,
+    pub webhook_secret_path: Option<PathBuf>,
+    pub socket_address: Option<String>,
+    pub path_prefix: Option<String>,
+    pub webhook_path: Option<String>,
+    pub health_path: Option<String>,
+    pub liveness_path: Option<String>,
+    pub readiness_path: Option<String>,
+    /// Seconds for which the result of the readiness probe's GitHub check is cached
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub readiness_cache_ttl: Option<String>,
+    /// Path of the diagnostics route, which is only served when the path is set
+    pub diagnostics_path: Option<String>,
+    /// Path of the Prometheus metrics route, which requires the `metrics` feature
+    pub metrics_path: Option<String>,
+    /// Either `production` or `development`, which reveals internal details in error responses
+    pub environment: Option<String>,
+    pub tls_certificate_path: Option<PathBuf>,
+    pub tls_key_path: Option<PathBuf>,
+    pub tls_redirect_address: Option<String>,
+    pub unix_socket_path: Option<PathBuf>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub unix_socket_mode: Option<String>,
+}
+
+/// Configuration that has been validated
+#[derive(Clone, Debug)]
+pub struct ValidatedConfig {
+    pub github_host: GitHubHost,
+    pub app_id: AppId,
+    pub private_key: PrivateKey,
+    pub webhook_secret: WebhookSecret,
+    pub socket_address: SocketAddr,
+    pub paths: Paths,
+    pub readiness_cache_ttl: Duration,
+    pub environment: Environment,
+    pub tls: Option<TlsConfig>,
+    pub unix_socket: Option<UnixSocketConfig>,
+}
+
+/// Environment that octox runs in
+///
+/// In production, error responses do not reveal the details of internal errors.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum Environment {
+    #[default]
+    Production,
+    Development,
+}
+
+impl Environment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Production => "production",
+            Environment::Development => "development",
+        }
+    }
+}
+
+/// Unix socket that the server listens on instead of the socket address
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct UnixSocketConfig {
+    pub path: PathBuf,
+    pub mode: Option<u32>,
+}
+
+/// Certificate chain and private key for HTTPS, which requires the `tls` feature
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TlsConfig {
+    pub certificate_path: PathBuf,
+    pub key_path: PathBuf,
+    pub redirect_address: Option<SocketAddr>,
+}
+
+/// Paths at which the routes are served
+///
+/// The paths of the built-in routes already include the prefix.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Paths {
+    pub prefix: String,
+    pub webhook: String,
+    pub health: String,
+    pub liveness: String,
+    pub readiness: String,
+    pub diagnostics: Option<String>,
+    pub metrics: Option<String>,
+}
+
+impl Paths {
+    /// Returns the path with the prefix, without a trailing slash
+    pub fn join(&self, path: &str) -> String {
+        match (self.prefix.as_str(), normalize_path(path)) {
+            ("", path) => path.into(),
+            (prefix, "/") => prefix.into(),
+            (prefix, path) => format!("{}{}", prefix, path),
+        }
+    }
+}
+
+/// Removes trailing slashes, so that e.g. `/hook` and `/hook/` are recognized as the same route
+fn normalize_path(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" if path.starts_with('/') => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// All problems that were found while validating a configuration
+#[derive(Clone, Eq, PartialEq, Debug, Error)]
+pub struct ConfigError {
+    problems: Vec<String>,
+}
+
+impl ConfigError {
+    pub(crate) fn new(problems: Vec<String>) -> Self {
+        Self { problems }
+    }
+
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "configuration is invalid:")?;
+
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl OctoxConfig {
+    /// Loads the configuration from a file, and overrides it with environment variables
+    pub fn load<P: AsRef<Path>>(path: Option<P>) -> Result<Self, Error> {
+        let config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+
+        Ok(config.merge(Self::from_env()))
+    }
+
+    /// Loads the configuration from a TOML or YAML file, depending on the file's extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        let content = read_to_string(path).map_err(|error| {
+            Error::Configuration(format!(
+                "failed to read config file {}: {}",
+                path.display(),
+                error
+            ))
+        })?;
+
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+
+        let config = match extension {
+            "toml" => toml::from_str(&content).map_err(|error| error.to_string()),
+            "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|error| error.to_string()),
+            _ => Err("config file must have the extension .toml, .yaml, or .yml".into()),
+        };
+
+        config.map_err(|error| {
+            Error::Configuration(format!(
+                "failed to parse config file {}: {}",
+                path.display(),
+                error
+            ))
+        })
+    }
+
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok();
+
+        Self {
+            github_host: var("OCTOX_GITHUB_HOST"),
+            app_id: var("OCTOX_APP_ID"),
+            private_key: var("OCTOX_PRIVATE_KEY"),
+            private_key_path: var("OCTOX_PRIVATE_KEY_PATH").map(PathBuf::from),
+            webhook_secret: var("OCTOX_WEBHOOK_SECRET"),
+            webhook_secret_path: var("OCTOX_WEBHOOK_SECRET_PATH").map(PathBuf::from),
+            socket_address: var("OCTOX_SOCKET_ADDRESS"),
+            path_prefix: var("OCTOX_PATH_PREFIX"),
+            webhook_path: var("OCTOX_WEBHOOK_PATH"),
+            health_path: var("OCTOX_HEALTH_PATH"),
+            liveness_path: var("OCTOX_LIVENESS_PATH"),
+            readiness_path: var("OCTOX_READINESS_PATH"),
+            readiness_cache_ttl: var("OCTOX_READINESS_CACHE_TTL"),
+            diagnostics_path: var("OCTOX_DIAGNOSTICS_PATH"),
+            metrics_path: var("OCTOX_METRICS_PATH"),
+            environment: var("OCTOX_ENVIRONMENT"),
+            tls_certificate_path: var("OCTOX_TLS_CERTIFICATE_PATH").map(PathBuf::from),
+            tls_key_path: var("OCTOX_TLS_KEY_PATH").map(PathBuf::from),
+            tls_redirect_address: var("OCTOX_TLS_REDIRECT_ADDRESS"),
+            unix_socket_path: var("OCTOX_UNIX_SOCKET_PATH").map(PathBuf::from),
+            unix_socket_mode: var("OCTOX_UNIX_SOCKET_MODE"),
+        }
+    }
+
+    /// Combines two configurations, with the values in `other` taking precedence
+    pub fn merge(self, other: OctoxConfig) -> Self {
+        Self {
+            github_host: other.github_host.or(self.github_host),
+            app_id: other.app_id.or(self.app_id),
+            private_key: other.private_key.or(self.private_key),
+            private_key_path: other.private_key_path.or(self.private_key_path),
+            webhook_secret: other.webhook_secret.or(self.webhook_secret),
+            webhook_secret_path: other.webhook_secret_path.or(self.webhook_secret_path),
+            socket_address: other.socket_address.or(self.socket_address),
+            path_prefix: other.path_prefix.or(self.path_prefix),
+            webhook_path: other.webhook_path.or(self.webhook_path),
+            health_path: other.health_path.or(self.health_path),
+            liveness_path: other.liveness_path.or(self.liveness_path),
+            readiness_path: other.readiness_path.or(self.readiness_path),
+            readiness_cache_ttl: other.readiness_cache_ttl.or(self.readiness_cache_ttl),
+            diagnostics_path: other.diagnostics_path.or(self.diagnostics_path),
+            metrics_path: other.metrics_path.or(self.metrics_path),
+            environment: other.environment.or(self.environment),
+            tls_certificate_path: other.tls_certificate_path.or(self.tls_certificate_path),
+            tls_key_path: other.tls_key_path.or(self.tls_key_path),
+            tls_redirect_address: other.tls_redirect_address.or(self.tls_redirect_address),
+            unix_socket_path: other.unix_socket_path.or(self.unix_socket_path),
+            unix_socket_mode: other.unix_socket_mode.or(self.unix_socket_mode),
+        }
+    }
+
+    /// Validates the configuration and reports all problems at once
+    pub fn validate(&self) -> Result<ValidatedConfig, ConfigError> {
+        let mut problems = Vec::new();
+
+        let github_host = self.validate_github_host().map_err(|p| problems.push(p));
+        let app_id = self.validate_app_id().map_err(|p| problems.push(p));
+        let private_key = self.validate_private_key().map_err(|p| problems.push(p));
+        let webhook_secret = self.validate_webhook_secret().map_err(|p| problems.push(p));
+        let socket_address = self.validate_socket_address().map_err(|p| problems.push(p));
+        let paths = self.validate_paths().map_err(|p| problems.extend(p));
+        let readiness_cache_ttl = self
+            .validate_readiness_cache_ttl()
+            .map_err(|p| problems.push(p));
+        let environment = self.validate_environment().map_err(|p| problems.push(p));
+        let tls = self.validate_tls().map_err(|p| problems.extend(p));
+        let unix_socket = self.validate_unix_socket().map_err(|p| problems.extend(p));
+
+        match (
+            github_host,
+            app_id,
+            private_key,
+            webhook_secret,
+            socket_address,
+            paths,
+            readiness_cache_ttl,
+            environment,
+            tls,
+            unix_socket,
+        ) {
+            (
+                Ok(github_host),
+                Ok(app_id),
+                Ok(private_key),
+                Ok(webhook_secret),
+                Ok(socket_address),
+                Ok(paths),
+                Ok(readiness_cache_ttl),
+                Ok(environment),
+                Ok(tls),
+                Ok(unix_socket),
+            ) => Ok(ValidatedConfig {
+                github_host,
+                app_id,
+                private_key,
+                webhook_secret,
+                socket_address,
+                paths,
+                readiness_cache_ttl,
+                environment,
+                tls,
+                unix_socket,
+            }),
+            _ => Err(ConfigError::new(problems)),
+        }
+    }
+
+    fn validate_github_host(&self) -> Result<GitHubHost, String> {
+        let github_host = self.github_host.as_deref().unwrap_or(DEFAULT_GITHUB_HOST);
+
+        if !github_host.starts_with("http://") && !github_host.starts_with("https://") {
+            return Err(format!(
+                "github host must be an HTTP or HTTPS URL, got `{}`",
+                github_host
+            ));
+        }
+
+        Ok(GitHubHost::new(github_host.into()))
+    }
+
+    fn validate_app_id(&self) -> Result<AppId, String> {
+        let app_id = self.app_id.as_deref().ok_or_else(|| {
+            String::from("app id must be set either in the config file or as OCTOX_APP_ID")
+        })?;
+
+        app_id
+            .trim()
+            .parse::<u64>()
+            .map(AppId::new)
+            .map_err(|_| format!("app id must be a number, got `{}`", app_id))
+    }
+
+    fn validate_private_key(&self) -> Result<PrivateKey, String> {
+        let private_key = match (&self.private_key, &self.private_key_path) {
+            (Some(private_key), _) => private_key.clone(),
+            (None, Some(path)) => read_to_string(path).map_err(|error| {
+                format!(
+                    "failed to read private key from {}: {}",
+                    path.display(),
+                    error
+                )
+            })?,
+            (None, None) => {
+                return Err("private key must be set either in the config file or as \
+                     OCTOX_PRIVATE_KEY or OCTOX_PRIVATE_KEY_PATH"
+                    .into())
+            }
+        };
+
+        if EncodingKey::from_rsa_pem(private_key.as_bytes()).is_err() {
+            return Err("private key must be an RSA key in PEM format".into());
+        }
+
+        Ok(PrivateKey::new(private_key))
+    }
+
+    fn validate_webhook_secret(&self) -> Result<WebhookSecret, String> {
+        let webhook_secret = match (&self.webhook_secret, &self.webhook_secret_path) {
+            (Some(webhook_secret), _) => webhook_secret.clone(),
+            // Secret files often end with a newline that is not part of the secret
+            (None, Some(path)) => read_to_string(path)
+                .map(|secret| secret.trim_end_matches(&['\r', '\n'][..]).into())
+                .map_err(|error| {
+                    format!(
+                        "failed to read webhook secret from {}: {}",
+                        path.display(),
+                        error
+                    )
+                })?,
+            (None, None) => {
+                return Err(
+                    "webhook secret must be set either in the config file or as \
+                     OCTOX_WEBHOOK_SECRET or OCTOX_WEBHOOK_SECRET_PATH"
+                        .into(),
+                )
+            }
+        };
+
+        if webhook_secret.is_empty() {
+            return Err("webhook secret must not be empty".into());
+        }
+
+        Ok(WebhookSecret::new(webhook_secret))
+    }
+
+    fn validate_socket_address(&self) -> Result<SocketAddr, String> {
+        let socket_address = self
+            .socket_address
+            .as_deref()
+            .unwrap_or(DEFAULT_SOCKET_ADDRESS);
+
+        socket_address.parse().map_err(|_| {
+            format!(
+                "socket address must be an IP address and port, got `{}`",
+                socket_address
+            )
+        })
+    }
+
+    fn validate_paths(&self) -> Result<Paths, Vec<String>> {
+        let mut problems = Vec::new();
+
+        let prefix = self.path_prefix.as_deref().unwrap_or_default();
+        if !prefix.is_empty() && (!prefix.starts_with('/') || prefix.ends_with('/')) {
+            problems.push(format!(
+                "path prefix must start with `/` and must not end with `/`, got `{}`",
+                prefix
+            ));
+        }
+
+        let routes = [
+            ("webhook", &self.webhook_path, Some(DEFAULT_WEBHOOK_PATH)),
+            ("health", &self.health_path, Some(DEFAULT_HEALTH_PATH)),
+            ("liveness", &self.liveness_path, Some(DEFAULT_LIVENESS_PATH)),
+            (
+                "readiness",
+                &self.readiness_path,
+                Some(DEFAULT_READINESS_PATH),
+            ),
+            ("diagnostics", &self.diagnostics_path, None),
+            (
+                "metrics",
+                &self.metrics_path,
+                cfg!(feature = "metrics").then(|| DEFAULT_METRICS_PATH),
+            ),
+        ]
+        .map(|(name, path, default)| (name, path.as_deref().or(default)));
+
+        let configured: Vec<(&str, &str)> = routes
+            .iter()
+            .filter_map(|(name, path)| path.map(|path| (*name, path)))
+            .collect();
+
+        for (index, (name, path)) in configured.iter().enumerate() {
+            if !path.starts_with('/') {
+                problems.push(format!("{} path must start with `/`, got `{}`", name, path));
+            }
+
+            for (other_name, other_path) in &configured[index + 1..] {
+                if normalize_path(path) == normalize_path(other_path) {
+                    problems.push(format!(
+                        "{} path and {} path must be different, both are `{}`",
+                        name, other_name, path
+                    ));
+                }
+            }
+        }
+
+        if self.metrics_path.is_some() && !cfg!(feature = "metrics") {
+            problems.push("metrics require octox to be built with the `metrics` feature".into());
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        let [webhook, health, liveness, readiness, diagnostics, metrics] =
+            routes.map(|(_, path)| path);
+        let mut paths = Paths {
+            prefix: prefix.into(),
+            webhook: String::new(),
+            health: String::new(),
+            liveness: String::new(),
+            readiness: String::new(),
+            diagnostics: None,
+            metrics: None,
+        };
+        paths.webhook = paths.join(webhook.unwrap_or_default());
+        paths.health = paths.join(health.unwrap_or_default());
+        paths.liveness = paths.join(liveness.unwrap_or_default());
+        paths.readiness = paths.join(readiness.unwrap_or_default());
+        paths.diagnostics = diagnostics.map(|path| paths.join(path));
+        paths.metrics = metrics.map(|path| paths.join(path));
+
+        Ok(paths)
+    }
+
+    fn validate_readiness_cache_ttl(&self) -> Result<Duration, String> {
+        match self.readiness_cache_ttl.as_deref() {
+            Some(ttl) => ttl.trim().parse().map(Duration::from_secs).map_err(|_| {
+                format!(
+                    "readiness cache TTL must be a number of seconds, got `{}`",
+                    ttl
+                )
+            }),
+            None => Ok(DEFAULT_READINESS_CACHE_TTL),
+        }
+    }
+
+    fn validate_environment(&self) -> Result<Environment, String> {
+        match self.environment.as_deref().map(str::trim) {
+            None | Some("production") => Ok(Environment::Production),
+            Some("development") => Ok(Environment::Development),
+            Some(environment) => Err(format!(
+                "environment must be `production` or `development`, got `{}`",
+                environment
+            )),
+        }
+    }
+
+    fn validate_tls(&self) -> Result<Option<TlsConfig>, Vec<String>> {
+        let mut problems = Vec::new();
+
+        let paths = match (&self.tls_certificate_path, &self.tls_key_path) {
+            (Some(certificate_path), Some(key_path)) => Some((certificate_path, key_path)),
+            (None, None) => None,
+            _ => {
+                problems.push("TLS certificate path and key path must be set together".into());
+                None
+            }
+        };
+
+        if paths.is_some() && !cfg!(feature = "tls") {
+            problems.push("TLS requires octox to be built with the `tls` feature".into());
+        }
+
+        let redirect_address = match self.tls_redirect_address.as_deref() {
+            Some(address) => match (address.parse(), paths.is_some()) {
+                (Ok(address), true) => Some(address),
+                (Ok(_), false) => {
+                    problems.push("TLS redirect address requires TLS to be configured".into());
+                    None
+                }
+                (Err(_), _) => {
+                    problems.push(format!(
+                        "TLS redirect address must be an IP address and port, got `{}`",
+                        address
+                    ));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        Ok(paths.map(|(certificate_path, key_path)| TlsConfig {
+            certificate_path: certificate_path.clone(),
+            key_path: key_path.clone(),
+            redirect_address,
+        }))
+    }
+
+    fn validate_unix_socket(&self) -> Result<Option<UnixSocketConfig>, Vec<String>> {
+        let mut problems = Vec::new();
+
+        let mode = match self.unix_socket_mode.as_deref() {
+            Some(mode) => u32::from_str_radix(mode, 8)
+                .map_err(|_| {
+                    problems.push(format!(
+                        "Unix socket mode must be an octal number, got `{}`",
+                        mode
+                    ))
+                })
+                .ok(),
+            None => None,
+        };
+
+        let path = match (&self.unix_socket_path, mode) {
+            (Some(path), _) => Some(path.clone()),
+            (None, Some(_)) => {
+                problems.push("Unix socket mode requires a Unix socket path".into());
+                None
+            }
+            (None, None) => None,
+        };
+
+        if path.is_some() && !cfg!(unix) {
+            problems.push("Unix sockets are only supported on Unix".into());
+        }
+
+        if path.is_some() && self.tls_certificate_path.is_some() {
+            problems.push("TLS is not supported on Unix sockets".into());
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+
+        Ok(path.map(|path| UnixSocketConfig { path, mode }))
+    }
+}
+
+fn string_or_number<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    let value = Option::<StringOrNumber>::deserialize(deserializer)?;
+
+    Ok(value.map(|value| match value {
+        StringOrNumber::String(string) => string,
+        StringOrNumber::Number(number) => number.to_string(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{Environment, OctoxConfig};
+
+    const PRIVATE_KEY: &str = include_str!("../tests/fixtures/private-key.pem");
+
+    fn valid_config() -> OctoxConfig {
+        OctoxConfig {
+            app_id: Some("1".into()),
+            private_key: Some(PRIVATE_KEY.into()),
+            webhook_secret: Some("secret".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_returns_defaults() {
+        let config = valid_config().validate().unwrap();
+
+        assert_eq!("https://api.github.com", config.github_host.get());
+        assert_eq!("127.0.0.1:3000", config.socket_address.to_string());
+    }
+
+    #[test]
+    fn validate_reports_all_problems() {
+        let config = OctoxConfig {
+            app_id: Some("not-a-number".into()),
+            private_key: Some("not-a-key".into()),
+            socket_address: Some("localhost".into()),
+            ..Default::default()
+        };
+
+        let error = config.validate().unwrap_err();
+
+        assert_eq!(4, error.problems().len(), "{}", error);
+        assert!(error.to_string().contains("app id must be a number"));
+        assert!(error.to_string().contains("RSA key in PEM format"));
+        assert!(error.to_string().contains("webhook secret must be set"));
+        assert!(error.to_string().contains("socket address must be"));
+    }
+
+    #[test]
+    fn validate_reports_unreadable_private_key() {
+        let config = OctoxConfig {
+            private_key: None,
+            private_key_path: Some("does/not/exist.pem".into()),
+            ..valid_config()
+        };
+
+        let error = config.validate().unwrap_err();
+
+        assert!(error.problems()[0].starts_with("failed to read private key from"));
+    }
+
+    #[test]
+    fn validate_reads_webhook_secret_from_file() {
+        let path = std::env::temp_dir().join("octox-webhook-secret");
+        std::fs::write(&path, "secret\n").unwrap();
+
+        let config = OctoxConfig {
+            webhook_secret: None,
+            webhook_secret_path: Some(path),
+            ..valid_config()
+        };
+
+        assert_eq!("secret", config.validate().unwrap().webhook_secret.get());
+    }
+
+    #[test]
+    fn validate_prefixes_paths() {
+        let config = OctoxConfig {
+            path_prefix: Some("/octox".into()),
+            health_path: Some("/healthz".into()),
+            ..valid_config()
+        };
+
+        let paths = config.validate().unwrap().paths;
+
+        assert_eq!("/octox", paths.webhook);
+        assert_eq!("/octox/healthz", paths.health);
+        assert_eq!("/octox/status", paths.join("/status"));
+        assert_eq!("/octox/status", paths.join("/status/"));
+    }
+
+    #[test]
+    fn validate_rejects_colliding_paths() {
+        let config = OctoxConfig {
+            webhook_path: Some("/github".into()),
+            health_path: Some("/github/".into()),
+            ..valid_config()
+        };
+
+        let error = config.validate().unwrap_err();
+
+        assert!(error.to_string().contains("must be different"));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_paths() {
+        let config = OctoxConfig {
+            path_prefix: Some("/octox/".into()),
+            webhook_path: Some("webhook".into()),
+            ..valid_config()
+        };
+
+        let error = config.validate().unwrap_err();
+
+        assert_eq!(2, error.problems().len(), "{}", error);
+    }
+
+    #[test]
+    fn validate_requires_tls_certificate_and_key() {
+        let config = OctoxConfig {
+            tls_certificate_path: Some("cert.pem".into()),
+            tls_redirect_address: Some("0.0.0.0:80".into()),
+            ..valid_config()
+        };
+
+        let error = config.validate().unwrap_err();
+
+        assert!(error.to_string().contains("must be set together"));
+        assert!(error.to_string().contains("requires TLS to be configured"));
+    }
+
+    #[test]
+    fn validate_parses_octal_unix_socket_mode() {
+        let config = OctoxConfig {
+            unix_socket_path: Some("/run/octox.sock".into()),
+            unix_socket_mode: Some("660".into()),
+            ..valid_config()
+        };
+
+        let unix_socket = config.validate().unwrap().unix_socket.unwrap();
+
+        assert_eq!(Some(0o660), unix_socket.mode);
+    }
+
+    #[test]
+    fn validate_defaults_to_production() {
+        let config = valid_config();
+
+        assert_eq!(
+            Environment::Production,
+            config.validate().unwrap().environment
+        );
+    }
+
+    #[test]
+    fn validate_rejects_unknown_environment() {
+        let config = OctoxConfig {
+            environment: Some("staging".into()),
+            ..valid_config()
+        };
+
+        let error = config.validate().unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("must be `production` or `development`"));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_unix_socket_mode() {
+        let config = OctoxConfig {
+            unix_socket_mode: Some("rw".into()),
+            ..valid_config()
+        };
+
+        let error = config.validate().unwrap_err();
+
+        assert!(error.to_string().contains("must be an octal number"));
+    }
+
+    #[test]
+    fn merge_prefers_other_values() {
+        let file = OctoxConfig {
+            app_id: Some("1".into()),
+            webhook_secret: Some("file".into()),
+            ..Default::default()
+        };
+        let env = OctoxConfig {
+            webhook_secret: Some("env".into()),
+            ..Default::default()
+        };
+
+        let config = file.merge(env);
+
+        assert_eq!(Some("1".into()), config.app_id);
+        assert_eq!(Some("env".into()), config.webhook_secret);
+    }
+
+    #[test]
+    fn from_file_reads_toml() {
+        let path = std::env::temp_dir().join("octox-from-file-reads.toml");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "app_id = 42\nwebhook_secret = \"secret\"").unwrap();
+
+        let config = OctoxConfig::from_file(&path).unwrap();
+
+        assert_eq!(Some("42".into()), config.app_id);
+        assert_eq!(Some("secret".into()), config.webhook_secret);
+    }
+
+    #[test]
+    fn from_file_reads_yaml() {
+        let path = std::env::temp_dir().join("octox-from-file-reads.yaml");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "app_id: \"42\"\nsocket_address: 0.0.0.0:8080").unwrap();
+
+        let config = OctoxConfig::from_file(&path).unwrap();
+
+        assert_eq!(Some("42".into()), config.app_id);
+        assert_eq!(Some("0.0.0.0:8080".into()), config.socket_address);
+    }
+
+    #[test]
+    fn from_file_rejects_unknown_fields() {
+        let path = std::env::temp_dir().join("octox-from-file-rejects.toml");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "app = 42").unwrap();
+
+        assert!(OctoxConfig::from_file(&path).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use serde_json::Value;
+
+/// Webhook delivery that triggered a workflow
+///
+/// The delivery is inserted into the workflow's [`State`](crate::State) next to the deserialized
+/// [`Event`](github_parts::event::Event). It gives steps access to the delivery's metadata and to
+/// fields of the payload that are not deserialized by `github-parts`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delivery {
+    /// Unique id of the delivery from the `X-GitHub-Delivery` header
+    pub id: Option<String>,
+    /// Type of the event from the `X-GitHub-Event` header, e.g. `check_run`
+    pub event: String,
+    /// Raw JSON payload of the webhook
+    pub payload: Value,
+}
+
+impl Delivery {
+    pub fn new(id: Option<String>, event: &str, payload: Value) -> Self {
+        Self {
+            id,
+            event: event.into(),
+            payload,
+        }
+    }
+
+    pub fn action(&self) -> Option<&str> {
+        self.payload["action"].as_str()
+    }
+
+    pub fn installation(&self) -> Option<u64> {
+        self.payload["installation"]["id"].as_u64()
+    }
+
+    pub fn repository(&self) -> Option<&str> {
+        self.payload["repository"]["full_name"].as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Delivery;
+
+    #[test]
+    fn delivery_returns_fields_from_payload() {
+        let delivery = Delivery::new(
+            None,
+            "check_run",
+            json!({
+                "action": "rerequested",
+                "installation": { "id": 1 },
+                "repository": { "full_name": "devxbots/octox" }
+            }),
+        );
+
+        assert_eq!(Some("rerequested"), delivery.action());
+        assert_eq!(Some(1), delivery.installation());
+        assert_eq!(Some("devxbots/octox"), delivery.repository());
+    }
+
+    #[test]
+    fn delivery_returns_none_for_missing_fields() {
+        let delivery = Delivery::new(None, "ping", json!({}));
+
+        assert_eq!(None, delivery.action());
+        assert_eq!(None, delivery.installation());
+    }
+}
@@ -3,6 +3,9 @@ use axum::response::{IntoResponse, Response};
 use thiserror::Error;
 
 use crate::auth::AuthError;
+use crate::client::GitHubError;
+use crate::config::{ConfigError, Environment};
+use crate::problem::{error_response, DevelopmentProblem, Problem};
 use crate::workflow::WorkflowError;
 
 #[derive(Debug, Error)]
@@ -10,15 +13,24 @@ pub enum Error {
     #[error("{0}")]
     Configuration(String),
 
+    #[error(transparent)]
+    InvalidConfiguration(#[from] ConfigError),
+
     #[error(transparent)]
     Client(#[from] AuthError),
 
     #[error(transparent)]
     ExternalResource(#[from] reqwest::Error),
 
-    #[error("failed to deserialize incoming request payload")]
+    #[error(transparent)]
+    GitHub(#[from] GitHubError),
+
+    #[error("failed to deserialize incoming request payload: {0}")]
     Payload(#[from] serde_json::Error),
 
+    #[error("server is shutting down and does not accept new deliveries")]
+    ShuttingDown,
+
     #[error(transparent)]
     Workflow(#[from] WorkflowError),
 
@@ -26,14 +38,52 @@ pub enum Error {
     UnexpectedError(#[from] anyhow::Error),
 }
 
+impl Error {
+    /// Stable, machine-readable code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Configuration(_) => "configuration_error",
+            Error::InvalidConfiguration(_) => "invalid_configuration",
+            Error::Client(error) => error.code(),
+            Error::ExternalResource(_) => "external_resource_error",
+            Error::GitHub(_) => "github_error",
+            Error::Payload(_) => "invalid_payload",
+            Error::ShuttingDown => "shutting_down",
+            Error::Workflow(error) => error.code(),
+            Error::UnexpectedError(_) => "unexpected_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Error::Client(error) => error.status(),
+            Error::Payload(_) => StatusCode::BAD_REQUEST,
+            Error::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            Error::Workflow(error) => error.status(),
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Responds with the error's problem document
+///
+/// The response does not know the environment, so it hides the details of server errors. The
+/// router replaces it with the detailed document in development, see [`show_problem_details`].
+///
+/// [`show_problem_details`]: crate::problem::show_problem_details
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        match self {
-            Error::Client(error) => error.into_response(),
-            _ => {
-                let body = self.to_string();
-                (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
-            }
+        let mut response = error_response(&self, Environment::Production, None);
+
+        if !self.status().is_success() {
+            response
+                .extensions_mut()
+                .insert(DevelopmentProblem(Problem::new(
+                    &self,
+                    Environment::Development,
+                )));
         }
+
+        response
     }
 }
@@ -0,0 +1,40 @@
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::{Request, Response};
+use axum::routing::Route;
+use axum::{BoxError, Router};
+use tower::{Layer, Service};
+
+/// Layer that was added with [`Octox::layer`](crate::Octox::layer)
+///
+/// The layer is stored as a function that applies it to a router, since the layer's type is erased
+/// when it is stored on the builder.
+pub(crate) struct CustomLayer(Box<dyn Fn(Router) -> Router + Send + Sync>);
+
+impl CustomLayer {
+    pub fn new<L, ResBody>(layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + Sync + 'static,
+        L::Service: Service<Request<Body>, Response = Response<ResBody>, Error = Infallible>
+            + Clone
+            + Send
+            + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+        ResBody: HttpBody<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<BoxError>,
+    {
+        Self(Box::new(move |router| router.layer(layer.clone())))
+    }
+
+    pub fn apply(&self, router: Router) -> Router {
+        (self.0)(router)
+    }
+}
+
+impl Debug for CustomLayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CustomLayer")
+    }
+}
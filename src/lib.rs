@@ -1,44 +1,100 @@
+use std::convert::Infallible;
 use std::fmt::{Display, Formatter};
-use std::fs::File;
-use std::io::Read;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
-use std::ops::Deref;
+use std::future::Future;
+use std::net::{SocketAddr, TcpListener};
+#[cfg(any(unix, feature = "tls"))]
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
-use axum::routing::{get, post};
-use axum::{Extension, Router, Server};
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::{Request, Response};
+use axum::middleware::from_fn;
+use axum::routing::{get, post, MethodRouter, Route};
+use axum::{BoxError, Extension, Router, Server};
 use github_parts::github::app::AppId;
 use github_parts::github::token::TokenFactory;
 use github_parts::github::{GitHubHost, PrivateKey, WebhookSecret};
 use parking_lot::Mutex;
+#[cfg(feature = "sentry")]
 use sentry_tower::{NewSentryLayer, SentryHttpLayer};
+use tower::{Layer, Service};
 use tower_http::trace::TraceLayer;
 
-use crate::routes::{health, webhook};
-
+use crate::auth::SignatureVerification;
+use crate::client::GitHubClient;
+use crate::layer::CustomLayer;
+use crate::listener::Listener;
+#[cfg(unix)]
+use crate::listener::UnixAccept;
+use crate::problem::show_problem_details;
+use crate::recording::Recorder;
+use crate::redelivery::Redelivery;
+use crate::reload::Reloader;
+use crate::report::ErrorReporters;
+use crate::routes::{diagnostics, health, liveness, readiness, webhook, GitHubProbe};
+use crate::shutdown::{run_until_shutdown, Deliveries, ShutdownSignal, DEFAULT_SHUTDOWN_TIMEOUT};
+use crate::tasks::BackgroundTasks;
+
+pub use self::config::{
+    ConfigError, Environment, OctoxConfig, Paths, TlsConfig, UnixSocketConfig, ValidatedConfig,
+};
+
+pub use self::delivery::Delivery;
 pub use self::error::Error;
+#[cfg(feature = "sentry")]
+pub use self::report::SentryReporter;
+pub use self::report::{ErrorContext, ErrorReporter};
+pub use self::shutdown::AbandonedDelivery;
 pub use self::state::State;
 pub use self::workflow::{Step, Transition, Workflow, WorkflowError};
 
 mod auth;
+pub mod checks;
+pub mod client;
+pub mod command;
+mod config;
+mod delivery;
 mod error;
+mod layer;
+mod listener;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod problem;
+pub mod recording;
+pub mod redelivery;
+mod reload;
+mod report;
 mod routes;
+mod shutdown;
 mod state;
+mod tasks;
+#[cfg(feature = "otel")]
+pub mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tls")]
+mod tls;
 mod workflow;
 
 type SharedTokenFactory = Arc<Mutex<TokenFactory>>;
 type WorkflowConstructor = fn(GitHubHost, AppId, PrivateKey) -> Box<dyn Workflow>;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Octox {
-    github_host: GitHubHost,
-    app_id: Option<AppId>,
-    private_key: Option<PrivateKey>,
-    webhook_secret: Option<WebhookSecret>,
-    socket_address: SocketAddr,
+    config: OctoxConfig,
+    overrides: OctoxConfig,
     tcp_listener: Option<TcpListener>,
     workflow: Option<WorkflowConstructor>,
+    shutdown_signal: Option<ShutdownSignal>,
+    shutdown_timeout: Option<Duration>,
+    routes: Vec<(String, MethodRouter)>,
+    layers: Vec<CustomLayer>,
+    error_reporters: Vec<Arc<dyn ErrorReporter>>,
+    redelivery_period: Option<Duration>,
+    recorder: Option<Arc<Recorder>>,
+    skip_signature_verification: bool,
 }
 
 impl Octox {
@@ -46,165 +102,484 @@ impl Octox {
         Self::default()
     }
 
+    /// Sets the configuration, e.g. one that was loaded with [`OctoxConfig::load`]
+    ///
+    /// Environment variables take precedence over the configuration, and the other builder
+    /// methods take precedence over both.
+    pub fn config(mut self, config: OctoxConfig) -> Result<Self, Error> {
+        self.config = config;
+        Ok(self)
+    }
+
     pub fn workflow(mut self, workflow: WorkflowConstructor) -> Result<Self, Error> {
         self.workflow = Some(workflow);
         Ok(self)
     }
 
     pub fn github_host(mut self, github_host: String) -> Result<Self, Error> {
-        self.github_host = GitHubHost::new(github_host);
+        self.overrides.github_host = Some(github_host);
         Ok(self)
     }
 
     pub fn app_id(mut self, app_id: u64) -> Result<Self, Error> {
-        self.app_id = Some(AppId::new(app_id));
+        self.overrides.app_id = Some(app_id.to_string());
         Ok(self)
     }
 
     pub fn private_key(mut self, private_key: &str) -> Result<Self, Error> {
-        self.private_key = Some(PrivateKey::new(private_key.into()));
+        self.overrides.private_key = Some(private_key.into());
         Ok(self)
     }
 
     pub fn webhook_secret(mut self, webhook_secret: &str) -> Result<Self, Error> {
-        self.webhook_secret = Some(WebhookSecret::new(webhook_secret.into()));
+        self.overrides.webhook_secret = Some(webhook_secret.into());
         Ok(self)
     }
 
     pub fn socket_address(mut self, address: SocketAddr) -> Result<Self, Error> {
-        self.socket_address = address;
+        self.overrides.socket_address = Some(address.to_string());
         self.tcp_listener = None;
         Ok(self)
     }
 
     pub fn tcp_listener(mut self, listener: TcpListener) -> Result<Self, Error> {
-        self.socket_address = listener
+        let address = listener
             .local_addr()
             .context("failed to get socket address from TCP listener")?;
 
+        self.overrides.socket_address = Some(address.to_string());
         self.tcp_listener = Some(listener);
 
         Ok(self)
     }
 
-    pub async fn serve(self) -> Result<(), Error> {
-        let app = Router::new()
-            .route("/", post(webhook))
-            .route("/health", get(health))
-            .layer(TraceLayer::new_for_http())
-            .layer(NewSentryLayer::new_from_top())
-            .layer(SentryHttpLayer::with_transaction())
-            .layer(self.github_host_extension()?)
-            .layer(self.token_factory_extension()?)
-            .layer(self.webhook_secret_extension()?)
-            .layer(self.workflow_extension()?);
-
-        let listener = match self.tcp_listener {
-            Some(listener) => listener,
-            None => {
-                TcpListener::bind(self.socket_address).context("failed to bind socket address")?
-            }
-        };
+    /// Serves HTTPS with the certificate chain and private key from the given PEM files
+    ///
+    /// The files are watched while the server is running, and are reloaded when they change.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, certificate_path: &Path, key_path: &Path) -> Result<Self, Error> {
+        self.overrides.tls_certificate_path = Some(certificate_path.into());
+        self.overrides.tls_key_path = Some(key_path.into());
+        Ok(self)
+    }
 
-        Server::from_tcp(listener)
-            .context("failed to create HTTP server from TCP listener")?
-            .serve(app.into_make_service())
-            .await
-            .context("failed to start HTTP server")?;
+    /// Redirects plain HTTP requests on the given address to HTTPS
+    #[cfg(feature = "tls")]
+    pub fn tls_redirect(mut self, address: SocketAddr) -> Result<Self, Error> {
+        self.overrides.tls_redirect_address = Some(address.to_string());
+        Ok(self)
+    }
 
-        Ok(())
+    /// Listens on a Unix socket instead of the socket address
+    ///
+    /// A stale socket file that was left behind by a previous process is removed, and the socket
+    /// file is removed again when the server shuts down.
+    #[cfg(unix)]
+    pub fn unix_socket(mut self, path: &Path) -> Result<Self, Error> {
+        self.overrides.unix_socket_path = Some(path.into());
+        Ok(self)
     }
 
-    fn workflow_extension(&self) -> Result<Extension<Arc<Box<dyn Workflow>>>, Error> {
-        if let Some(workflow) = &self.workflow {
-            let github_host = self.github_host.clone();
-            let app_id = self.try_app_id()?;
-            let private_key = self.try_private_key()?;
+    /// Sets the permissions of the Unix socket file, e.g. `0o660`
+    #[cfg(unix)]
+    pub fn unix_socket_mode(mut self, mode: u32) -> Result<Self, Error> {
+        self.overrides.unix_socket_mode = Some(format!("{:o}", mode));
+        Ok(self)
+    }
 
-            let constructor = workflow.deref();
-            let workflow = constructor(github_host, app_id, private_key);
+    /// Sets a prefix for all routes, e.g. `/octox`
+    pub fn path_prefix(mut self, prefix: &str) -> Result<Self, Error> {
+        self.overrides.path_prefix = Some(prefix.into());
+        Ok(self)
+    }
 
-            return Ok(Extension(Arc::new(workflow)));
-        }
+    /// Sets the path of the webhook route, which defaults to `/`
+    pub fn webhook_path(mut self, path: &str) -> Result<Self, Error> {
+        self.overrides.webhook_path = Some(path.into());
+        Ok(self)
+    }
 
-        Err(Error::Configuration("workflow must be set".into()))
+    /// Sets the path of the health route, which defaults to `/health`
+    pub fn health_path(mut self, path: &str) -> Result<Self, Error> {
+        self.overrides.health_path = Some(path.into());
+        Ok(self)
     }
 
-    fn github_host_extension(&self) -> Result<Extension<GitHubHost>, Error> {
-        Ok(Extension(self.github_host.clone()))
+    /// Sets the path of the liveness route, which defaults to `/livez`
+    pub fn liveness_path(mut self, path: &str) -> Result<Self, Error> {
+        self.overrides.liveness_path = Some(path.into());
+        Ok(self)
     }
 
-    fn token_factory_extension(&self) -> Result<Extension<SharedTokenFactory>, Error> {
-        let github_host = self.github_host.clone();
-        let app_id = self.try_app_id()?;
-        let private_key = self.try_private_key()?;
+    /// Sets the path of the readiness route, which defaults to `/readyz`
+    pub fn readiness_path(mut self, path: &str) -> Result<Self, Error> {
+        self.overrides.readiness_path = Some(path.into());
+        Ok(self)
+    }
+
+    /// Sets how long the readiness route caches the result of its GitHub check
+    ///
+    /// The health route shares the cached result. The default is 30 seconds.
+    pub fn readiness_cache_ttl(mut self, ttl: Duration) -> Result<Self, Error> {
+        self.overrides.readiness_cache_ttl = Some(ttl.as_secs().to_string());
+        Ok(self)
+    }
 
-        let factory = TokenFactory::new(github_host, app_id, private_key);
-        let shared_factory = Arc::new(Mutex::new(factory));
+    /// Serves a diagnostics document about the app and its connection to GitHub at the path
+    ///
+    /// The document shows which app octox is authenticated as, and its rate limits. It is not
+    /// served by default, and the path should not be exposed publicly.
+    pub fn diagnostics_path(mut self, path: &str) -> Result<Self, Error> {
+        self.overrides.diagnostics_path = Some(path.into());
+        Ok(self)
+    }
 
-        Ok(Extension(shared_factory))
+    /// Sets the path of the Prometheus metrics route
+    ///
+    /// The default is `/metrics`.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_path(mut self, path: &str) -> Result<Self, Error> {
+        self.overrides.metrics_path = Some(path.into());
+        Ok(self)
     }
 
-    fn webhook_secret_extension(&self) -> Result<Extension<WebhookSecret>, Error> {
-        if let Some(webhook_secret) = &self.webhook_secret {
-            return Ok(Extension(webhook_secret.clone()));
-        }
+    /// Sets the environment that octox runs in
+    ///
+    /// The default is production, in which error responses do not reveal internal details.
+    pub fn environment(mut self, environment: Environment) -> Result<Self, Error> {
+        self.overrides.environment = Some(environment.as_str().into());
+        Ok(self)
+    }
+
+    /// Periodically asks GitHub to redeliver webhook deliveries that failed
+    ///
+    /// The first run starts with the server, which recovers the deliveries that were missed while
+    /// the app was down. See [`Redelivery`] for the limits that apply.
+    ///
+    /// Only [`serve`](Self::serve) runs the redelivery, not [`router`](Self::router). Every instance
+    /// that enables it scans the deliveries on its own, so enable it on a single instance when the
+    /// app runs with several replicas.
+    pub fn redeliver_failed_deliveries(mut self, period: Duration) -> Result<Self, Error> {
+        self.redelivery_period = Some(period);
+        Ok(self)
+    }
+
+    /// Records the deliveries that the webhook route accepts
+    ///
+    /// Recordings can be replayed with [`recording::Replay`], e.g. to debug a production issue
+    /// locally.
+    pub fn record_deliveries(mut self, recorder: Recorder) -> Result<Self, Error> {
+        self.recorder = Some(Arc::new(recorder));
+        Ok(self)
+    }
+
+    /// Accepts webhook deliveries without verifying their signatures
+    ///
+    /// This is only allowed in the development environment, where it makes it possible to replay
+    /// recorded deliveries without knowing the webhook secret.
+    pub fn skip_signature_verification(mut self) -> Result<Self, Error> {
+        self.skip_signature_verification = true;
+        Ok(self)
+    }
+
+    /// Sets the future that starts a graceful shutdown when it resolves
+    ///
+    /// By default, the server shuts down when it receives `SIGTERM` or `Ctrl+C`.
+    pub fn shutdown_signal(
+        mut self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<Self, Error> {
+        self.shutdown_signal = Some(ShutdownSignal::new(signal));
+        Ok(self)
+    }
 
-        if let Ok(webhook_secret) = std::env::var("OCTOX_WEBHOOK_SECRET") {
-            return Ok(Extension(WebhookSecret::new(webhook_secret)));
+    /// Sets how long running workflows can take to finish during a shutdown
+    ///
+    /// Deliveries that are still running after the timeout are abandoned, logged, and returned by
+    /// [`Octox::serve`]. The default is 30 seconds.
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Result<Self, Error> {
+        self.shutdown_timeout = Some(timeout);
+        Ok(self)
+    }
+
+    /// Adds a route next to the webhook and health routes
+    ///
+    /// The path is relative to the path prefix, if one is configured.
+    ///
+    /// Custom routes are wrapped by the built-in layers, so they are traced, reported to Sentry if
+    /// the `sentry` feature is enabled, and can use the same extensions as the built-in routes,
+    /// e.g. `Extension<GitHubClient>`.
+    /// Layers that only apply to a single route, e.g. authentication, can be added to the route
+    /// itself with [`MethodRouter::layer`].
+    pub fn route(mut self, path: &str, method_router: MethodRouter) -> Result<Self, Error> {
+        if !path.starts_with('/') {
+            return Err(Error::Configuration(format!(
+                "route path must start with `/`, got `{}`",
+                path
+            )));
         }
 
-        Err(Error::Configuration(
-            "webhook secret must be set either manually or as an environment variable".into(),
-        ))
+        self.routes.push((path.into(), method_router));
+        Ok(self)
+    }
+
+    /// Adds a backend that errors are reported to
+    ///
+    /// Errors that occur while processing a webhook delivery are reported to all backends, together
+    /// with the delivery's context. With the `sentry` feature, errors are also reported to Sentry.
+    pub fn error_reporter(mut self, reporter: impl ErrorReporter + 'static) -> Result<Self, Error> {
+        self.error_reporters.push(Arc::new(reporter));
+        Ok(self)
     }
 
-    fn try_app_id(&self) -> Result<AppId, Error> {
-        if let Some(app_id) = self.app_id {
-            return Ok(app_id);
+    /// Adds a layer around all routes
+    ///
+    /// Custom layers wrap the built-in layers, so they see requests before and responses after
+    /// the built-in ones. When multiple layers are added, the last one is the outermost.
+    pub fn layer<L, ResBody>(mut self, layer: L) -> Result<Self, Error>
+    where
+        L: Layer<Route> + Clone + Send + Sync + 'static,
+        L::Service: Service<Request<Body>, Response = Response<ResBody>, Error = Infallible>
+            + Clone
+            + Send
+            + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+        ResBody: HttpBody<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<BoxError>,
+    {
+        self.layers.push(CustomLayer::new(layer));
+        Ok(self)
+    }
+
+    /// Validates the configuration and reports all problems at once
+    ///
+    /// The configuration is combined from the config file, environment variables, and the builder
+    /// methods, in increasing order of precedence.
+    pub fn validate(&self) -> Result<ValidatedConfig, Error> {
+        self.validate_config(self.merged_config())
+    }
+
+    fn validate_config(&self, config: OctoxConfig) -> Result<ValidatedConfig, Error> {
+        let mut problems = Vec::new();
+
+        let config = config
+            .validate()
+            .map_err(|error| problems.extend_from_slice(error.problems()));
+
+        if self.workflow.is_none() {
+            problems.push("workflow must be set".into());
         }
 
-        let app_id_from_env = match std::env::var("OCTOX_APP_ID") {
-            Ok(app_id) => app_id,
-            Err(_) => {
-                return Err(Error::Configuration(
-                    "app id must be set either manually or as an environment variable".into(),
-                ))
+        if let Ok(config) = &config {
+            if self.skip_signature_verification && config.environment != Environment::Development {
+                problems.push(
+                    "signature verification can only be skipped in the development environment"
+                        .into(),
+                );
             }
-        };
 
-        if let Ok(app_id) = app_id_from_env.parse::<u64>() {
-            return Ok(AppId::new(app_id));
+            let mut paths = vec![
+                config.paths.webhook.clone(),
+                config.paths.health.clone(),
+                config.paths.liveness.clone(),
+                config.paths.readiness.clone(),
+            ];
+            paths.extend(config.paths.diagnostics.clone());
+            paths.extend(config.paths.metrics.clone());
+
+            for (path, _) in &self.routes {
+                let path = config.paths.join(path);
+
+                if paths.contains(&path) {
+                    problems.push(format!("route `{}` is defined more than once", path));
+                }
+
+                paths.push(path);
+            }
         }
 
-        Err(Error::Configuration("app id must be a number".into()))
+        match config {
+            Ok(config) if problems.is_empty() => Ok(config),
+            _ => Err(ConfigError::new(problems).into()),
+        }
     }
 
-    fn try_private_key(&self) -> Result<PrivateKey, Error> {
-        if let Some(private_key) = &self.private_key {
-            return Ok(private_key.clone());
-        }
+    /// Returns the router with the webhook and health routes
+    ///
+    /// The router can be nested into an existing axum application, e.g. under `/github`. It must be
+    /// created inside a Tokio runtime, since it starts watching the credentials for changes. The
+    /// background tasks stop when the router and all of its clones have been dropped. The router
+    /// does not redeliver failed deliveries, even if
+    /// [`redeliver_failed_deliveries`](Self::redeliver_failed_deliveries) is set.
+    pub fn router(&self) -> Result<Router, Error> {
+        let config = self.validate()?;
+        self.build_router(&config, Deliveries::new(), None)
+    }
 
-        if let Ok(private_key) = std::env::var("OCTOX_PRIVATE_KEY") {
-            return Ok(PrivateKey::new(private_key));
+    /// Serves the app until the shutdown signal resolves
+    ///
+    /// The server listens on the first socket that is configured, in this order: the TCP listener,
+    /// a socket passed by systemd through `LISTEN_FDS`, the Unix socket, and the socket address.
+    ///
+    /// When the signal resolves, new deliveries are refused with `503 Service Unavailable` while
+    /// the running workflows get until the shutdown timeout to finish. The deliveries that did not
+    /// finish in time are returned, e.g. to redeliver them once the app is back up.
+    pub async fn serve(mut self) -> Result<Vec<AbandonedDelivery>, Error> {
+        let config = self.validate()?;
+        let deliveries = Deliveries::new();
+
+        // Sockets passed by systemd are only taken when the caller did not provide a listener
+        let listener = match self.tcp_listener.take() {
+            Some(listener) => Listener::Tcp(listener),
+            None => match Listener::from_systemd()? {
+                Some(listener) => listener,
+                None => Listener::bind(&config)?,
+            },
         };
+        listener.check_tls(config.tls.as_ref())?;
+
+        let app = self.build_router(&config, deliveries.clone(), self.redelivery_period)?;
+
+        let signal = self
+            .shutdown_signal
+            .unwrap_or_else(ShutdownSignal::terminate);
+        let timeout = self.shutdown_timeout.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT);
+
+        let draining = deliveries.clone();
+        let shutdown = async move {
+            signal.wait().await;
+            draining.drain();
+        };
+
+        match listener {
+            Listener::Tcp(listener) => {
+                #[cfg(feature = "tls")]
+                if let Some(tls) = &config.tls {
+                    return tls::serve(listener, app, tls, shutdown, &deliveries, timeout).await;
+                }
 
-        let private_key_path = match std::env::var("OCTOX_PRIVATE_KEY_PATH") {
-            Ok(path) => path,
-            Err(_) => {
-                return Err(Error::Configuration(
-                    "private key must be set either manually or as an environment variable".into(),
-                ))
+                let server = Server::from_tcp(listener)
+                    .context("failed to create HTTP server from TCP listener")?
+                    .serve(app.into_make_service())
+                    .with_graceful_shutdown(shutdown);
+
+                run_until_shutdown(server, &deliveries, timeout).await
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, path) => {
+                let accept = UnixAccept::new(listener)
+                    .context("failed to create HTTP server from Unix listener")?;
+                let server = Server::builder(accept)
+                    .serve(app.into_make_service())
+                    .with_graceful_shutdown(shutdown);
+
+                let result = run_until_shutdown(server, &deliveries, timeout).await;
+
+                if let Some(path) = path {
+                    std::fs::remove_file(path).ok();
+                }
+
+                result
             }
+        }
+    }
+
+    fn build_router(
+        &self,
+        config: &ValidatedConfig,
+        deliveries: Deliveries,
+        redelivery_period: Option<Duration>,
+    ) -> Result<Router, Error> {
+        let reloader = self.reloader(config.clone())?;
+        let github_client =
+            GitHubClient::with_token_factory(config.github_host.clone(), reloader.token_factory());
+
+        let mut tasks = BackgroundTasks::default();
+
+        if let Some(period) = redelivery_period {
+            tasks.spawn(Redelivery::new(github_client.clone()).run_every(period));
+        }
+
+        let verification = if self.skip_signature_verification {
+            tracing::warn!("webhook signatures are not verified");
+            SignatureVerification::Skipped
+        } else {
+            SignatureVerification::Required
         };
 
-        let mut private_key = String::new();
-        File::open(private_key_path)
-            .context("failed to open private key")?
-            .read_to_string(&mut private_key)
-            .context("failed to read private key")?;
+        let mut router = Router::new()
+            .route(&config.paths.webhook, post(webhook))
+            .route(&config.paths.health, get(health))
+            .route(&config.paths.liveness, get(liveness))
+            .route(&config.paths.readiness, get(readiness));
+
+        if let Some(path) = &config.paths.diagnostics {
+            router = router.route(path, get(diagnostics));
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(path) = &config.paths.metrics {
+            router = router.route(path, get(crate::routes::metrics));
+        }
 
-        Ok(PrivateKey::new(private_key))
+        for (path, method_router) in &self.routes {
+            router = router.route(&config.paths.join(path), method_router.clone());
+        }
+
+        let environment = config.environment;
+        let router = router
+            .layer(from_fn(move |request, next| {
+                show_problem_details(request, next, environment)
+            }))
+            .layer(TraceLayer::new_for_http());
+
+        #[cfg(feature = "sentry")]
+        let router = router
+            .layer(NewSentryLayer::new_from_top())
+            .layer(SentryHttpLayer::with_transaction());
+
+        let error_reporters = self.error_reporters.iter().cloned();
+        #[cfg(feature = "sentry")]
+        let error_reporters =
+            error_reporters.chain([Arc::new(SentryReporter) as Arc<dyn ErrorReporter>]);
+
+        let mut router = router
+            .layer(Extension(ErrorReporters::new(error_reporters.collect())))
+            .layer(Extension(config.environment))
+            .layer(Extension(verification))
+            .layer(Extension(self.recorder.clone()))
+            .layer(Extension(config.github_host.clone()))
+            .layer(Extension(GitHubProbe::new(
+                github_client.clone(),
+                config.readiness_cache_ttl,
+            )))
+            .layer(Extension(github_client))
+            .layer(Extension(reloader.webhook_secret()))
+            .layer(Extension(reloader.workflow()))
+            .layer(Extension(deliveries));
+
+        for layer in &self.layers {
+            router = layer.apply(router);
+        }
+
+        tasks.spawn(reloader.watch());
+
+        Ok(router.layer(Extension(Arc::new(tasks))))
+    }
+
+    fn merged_config(&self) -> OctoxConfig {
+        self.config
+            .clone()
+            .merge(OctoxConfig::from_env())
+            .merge(self.overrides.clone())
+    }
+
+    fn reloader(&self, config: ValidatedConfig) -> Result<Reloader, Error> {
+        let constructor = self
+            .workflow
+            .ok_or_else(|| Error::Configuration("workflow must be set".into()))?;
+
+        Ok(Reloader::new(self.merged_config(), config, constructor))
     }
 }
 
@@ -214,34 +589,77 @@ impl Display for Octox {
     }
 }
 
-impl Default for Octox {
-    fn default() -> Self {
-        let github_host = "https://api.github.com".into();
-        let socket_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 3000);
-
-        Self {
-            github_host,
-            app_id: None,
-            private_key: None,
-            webhook_secret: None,
-            socket_address,
-            tcp_listener: None,
-            workflow: None,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use std::net::{SocketAddr, TcpListener};
 
-    use super::{Error, Octox};
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use github_parts::github::app::AppId;
+    use github_parts::github::{GitHubHost, PrivateKey};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use crate::auth::sign;
+    use crate::recording::Recorder;
+    use crate::shutdown::Deliveries;
+
+    use super::{
+        Environment, Error, Octox, OctoxConfig, State, Step, Transition, ValidatedConfig, Workflow,
+        WorkflowError,
+    };
+
+    const PRIVATE_KEY: &str = include_str!("../tests/fixtures/private-key.pem");
+
+    #[derive(Debug)]
+    struct Noop;
+
+    impl Workflow for Noop {
+        fn initial_step(&self) -> Box<dyn Step> {
+            Box::new(NoopStep)
+        }
+    }
+
+    struct NoopStep;
+
+    #[async_trait]
+    impl Step for NoopStep {
+        async fn next(self: Box<Self>, _state: &mut State) -> Result<Transition, WorkflowError> {
+            Ok(Transition::Complete(json!(null)))
+        }
+    }
+
+    fn noop(_: GitHubHost, _: AppId, _: PrivateKey) -> Box<dyn Workflow> {
+        Box::new(Noop)
+    }
+
+    /// Validates the configuration without `OCTOX_*` variables from the environment of the test
+    fn validate_without_env(octox: &Octox) -> Result<ValidatedConfig, Error> {
+        octox.validate_config(octox.config.clone().merge(octox.overrides.clone()))
+    }
 
     #[test]
     fn new_returns_default_instance() {
         let octox = Octox::new();
 
-        assert_eq!("127.0.0.1:3000", octox.socket_address.to_string());
+        assert_eq!(OctoxConfig::default(), octox.config);
+        assert_eq!(OctoxConfig::default(), octox.overrides);
+    }
+
+    #[test]
+    fn config_sets_config() -> Result<(), Error> {
+        let config = OctoxConfig {
+            app_id: Some("1".into()),
+            ..Default::default()
+        };
+
+        let octox = Octox::new().config(config.clone())?;
+
+        assert_eq!(config, octox.config);
+        Ok(())
     }
 
     #[test]
@@ -250,7 +668,7 @@ mod tests {
 
         let octox = octox.github_host("github_host".into())?;
 
-        assert_eq!("github_host", octox.github_host.get());
+        assert_eq!(Some("github_host"), octox.overrides.github_host.as_deref());
         Ok(())
     }
 
@@ -260,7 +678,7 @@ mod tests {
 
         let octox = octox.app_id(1)?;
 
-        assert!(octox.app_id.is_some());
+        assert_eq!(Some("1"), octox.overrides.app_id.as_deref());
         Ok(())
     }
 
@@ -270,7 +688,7 @@ mod tests {
 
         let octox = octox.private_key("private_key")?;
 
-        assert!(octox.private_key.is_some());
+        assert!(octox.overrides.private_key.is_some());
         Ok(())
     }
 
@@ -280,7 +698,7 @@ mod tests {
 
         let octox = octox.webhook_secret("webhook_secret")?;
 
-        assert!(octox.webhook_secret.is_some());
+        assert!(octox.overrides.webhook_secret.is_some());
         Ok(())
     }
 
@@ -290,7 +708,10 @@ mod tests {
 
         let octox = octox.socket_address("127.0.0.1:8000".parse::<SocketAddr>().unwrap())?;
 
-        assert_eq!("127.0.0.1:8000", octox.socket_address.to_string());
+        assert_eq!(
+            Some("127.0.0.1:8000"),
+            octox.overrides.socket_address.as_deref()
+        );
         Ok(())
     }
 
@@ -302,7 +723,10 @@ mod tests {
             .tcp_listener(TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap())?;
         let octox = octox.socket_address("127.0.0.1:8000".parse::<SocketAddr>().unwrap())?;
 
-        assert_eq!("127.0.0.1:8000", octox.socket_address.to_string());
+        assert_eq!(
+            Some("127.0.0.1:8000"),
+            octox.overrides.socket_address.as_deref()
+        );
         assert!(octox.tcp_listener.is_none());
         Ok(())
     }
@@ -316,7 +740,223 @@ mod tests {
 
         let octox = octox.tcp_listener(tcp_listener)?;
 
-        assert_eq!(address, octox.socket_address);
+        assert_eq!(Some(address.to_string()), octox.overrides.socket_address);
+        Ok(())
+    }
+
+    #[test]
+    fn builder_overrides_config() -> Result<(), Error> {
+        let config = OctoxConfig {
+            app_id: Some("1".into()),
+            private_key: Some(PRIVATE_KEY.into()),
+            webhook_secret: Some("secret".into()),
+            socket_address: Some("127.0.0.1:8000".into()),
+            ..Default::default()
+        };
+
+        let octox = Octox::new()
+            .config(config)?
+            .app_id(2)?
+            .socket_address("127.0.0.1:9000".parse::<SocketAddr>().unwrap())?
+            .workflow(noop)?;
+
+        let validated = validate_without_env(&octox)?;
+
+        assert_eq!("127.0.0.1:9000", validated.socket_address.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_routes() -> Result<(), Error> {
+        let octox = Octox::new()
+            .app_id(1)?
+            .private_key(PRIVATE_KEY)?
+            .webhook_secret("secret")?
+            .route("/health", get(|| async { "healthy" }))?
+            .route("/status", get(|| async { "running" }))?
+            .route("/status", get(|| async { "running" }))?;
+
+        let error = validate_without_env(&octox).unwrap_err().to_string();
+
+        assert!(
+            error.contains("route `/health` is defined more than once"),
+            "{}",
+            error
+        );
+        assert!(error.contains("route `/status` is defined more than once"));
+        Ok(())
+    }
+
+    #[test]
+    fn validate_rejects_routes_that_only_differ_in_trailing_slash() -> Result<(), Error> {
+        let octox = Octox::new()
+            .app_id(1)?
+            .private_key(PRIVATE_KEY)?
+            .webhook_secret("secret")?
+            .route("/status", get(|| async { "running" }))?
+            .route("/status/", get(|| async { "running" }))?
+            .workflow(noop)?;
+
+        let error = validate_without_env(&octox).unwrap_err().to_string();
+
+        assert!(
+            error.contains("route `/status` is defined more than once"),
+            "{}",
+            error
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn validate_prefixes_custom_routes() -> Result<(), Error> {
+        let octox = Octox::new()
+            .app_id(1)?
+            .private_key(PRIVATE_KEY)?
+            .webhook_secret("secret")?
+            .path_prefix("/octox")?
+            .health_path("/healthz")?
+            .route("/octox/healthz", get(|| async { "healthy" }))?
+            .route("/healthz", get(|| async { "healthy" }))?
+            .workflow(noop)?;
+
+        let error = validate_without_env(&octox).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("route `/octox/healthz` is defined more than once"));
+        assert!(!error.to_string().contains("/octox/octox/healthz"));
+        Ok(())
+    }
+
+    #[test]
+    fn route_requires_leading_slash() {
+        assert!(Octox::new().route("status", get(|| async { "" })).is_err());
+    }
+
+    #[test]
+    fn validate_only_skips_signature_verification_in_development() -> Result<(), Error> {
+        let octox = Octox::new()
+            .app_id(1)?
+            .private_key(PRIVATE_KEY)?
+            .webhook_secret("secret")?
+            .skip_signature_verification()?
+            .workflow(noop)?;
+
+        let error = validate_without_env(&octox).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("signature verification can only be skipped in the development environment"));
+
+        let octox = octox.environment(Environment::Development)?;
+        assert!(validate_without_env(&octox).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_requires_workflow() {
+        let octox = Octox::new().app_id(1).unwrap();
+
+        let error = validate_without_env(&octox).unwrap_err();
+
+        assert!(error.to_string().contains("workflow must be set"));
+    }
+
+    async fn failing_route_detail(environment: Environment) -> Result<Option<String>, Error> {
+        let octox = Octox::new()
+            .app_id(1)?
+            .private_key(PRIVATE_KEY)?
+            .webhook_secret("secret")?
+            .environment(environment)?
+            .route(
+                "/fail",
+                get(|| async { Err::<(), _>(Error::UnexpectedError(anyhow!("database is down"))) }),
+            )?
+            .workflow(noop)?;
+        let config = validate_without_env(&octox)?;
+        let router = octox.build_router(&config, Deliveries::new(), None)?;
+
+        let request = Request::get("/fail").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body)?;
+
+        Ok(problem["detail"].as_str().map(String::from))
+    }
+
+    #[tokio::test]
+    async fn custom_route_errors_show_details_in_development() -> Result<(), Error> {
+        assert_eq!(
+            Some("database is down".into()),
+            failing_route_detail(Environment::Development).await?
+        );
+        assert_eq!(None, failing_route_detail(Environment::Production).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn webhook_refuses_deliveries_while_draining() -> Result<(), Error> {
+        let octox = Octox::new()
+            .app_id(1)?
+            .private_key(PRIVATE_KEY)?
+            .webhook_secret("secret")?
+            .workflow(noop)?;
+        let config = validate_without_env(&octox)?;
+        let deliveries = Deliveries::new();
+        let router = octox.build_router(&config, deliveries.clone(), None)?;
+
+        deliveries.drain();
+
+        let body = include_str!("../tests/fixtures/check_run.created.json");
+        let request = Request::post("/")
+            .header("X-GitHub-Event", "check_run")
+            .header(
+                "X-Hub-Signature-256",
+                sign(body.as_bytes(), &config.webhook_secret)?,
+            )
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn webhook_refuses_deliveries_while_draining_before_recording_them() -> Result<(), Error>
+    {
+        let directory = std::env::temp_dir().join("octox-refuses-deliveries-while-draining");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let octox = Octox::new()
+            .app_id(1)?
+            .private_key(PRIVATE_KEY)?
+            .webhook_secret("secret")?
+            .record_deliveries(Recorder::new(&directory))?
+            .workflow(noop)?;
+        let config = validate_without_env(&octox)?;
+        let deliveries = Deliveries::new();
+        let router = octox.build_router(&config, deliveries.clone(), None)?;
+
+        deliveries.drain();
+
+        // The payload is not parsed, so a malformed payload does not turn the response into a 400
+        let body = "{";
+        let request = Request::post("/")
+            .header("X-GitHub-Event", "check_run")
+            .header(
+                "X-Hub-Signature-256",
+                sign(body.as_bytes(), &config.webhook_secret)?,
+            )
+            .body(Body::from(body))
+            .unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert!(!directory.exists());
         Ok(())
     }
 }
@@ -0,0 +1,306 @@
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use crate::config::{TlsConfig, ValidatedConfig};
+use crate::Error;
+
+#[cfg(unix)]
+pub(crate) use self::unix::UnixAccept;
+
+/// Socket that the server accepts connections on
+#[derive(Debug)]
+pub(crate) enum Listener {
+    Tcp(TcpListener),
+    /// Unix socket, with the path of the socket file if octox created it and must remove it
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener, Option<PathBuf>),
+}
+
+impl Listener {
+    /// Binds the Unix socket if one is configured, and the socket address otherwise
+    pub fn bind(config: &ValidatedConfig) -> Result<Self, Error> {
+        #[cfg(unix)]
+        if let Some(unix_socket) = &config.unix_socket {
+            return Self::bind_unix(unix_socket);
+        }
+
+        let listener =
+            TcpListener::bind(config.socket_address).context("failed to bind socket address")?;
+
+        Ok(Listener::Tcp(listener))
+    }
+
+    /// Returns the listener that was passed to the process by systemd's socket activation
+    ///
+    /// systemd passes sockets as file descriptors starting at 3, and sets `LISTEN_FDS` to their
+    /// number and `LISTEN_PID` to the process that they are meant for. octox serves on a single
+    /// socket, and ignores any further ones.
+    ///
+    /// The variables are not removed from the environment, since that is not safe while other
+    /// threads are running. Child processes ignore them anyway, because `LISTEN_PID` does not
+    /// match their process id.
+    pub fn from_systemd() -> Result<Option<Self>, Error> {
+        let count = inherited_fds(
+            std::env::var("LISTEN_PID").ok().as_deref(),
+            std::env::var("LISTEN_FDS").ok().as_deref(),
+            std::process::id(),
+        );
+
+        if count == 0 {
+            return Ok(None);
+        }
+
+        if count > 1 {
+            tracing::warn!(
+                count,
+                "systemd passed more than one socket, using the first one"
+            );
+        }
+
+        Self::from_fd(SD_LISTEN_FDS_START).map(Some)
+    }
+
+    /// Fails if TLS is configured for a Unix socket
+    ///
+    /// The configuration rejects a Unix socket path together with TLS, but systemd can pass a Unix
+    /// socket regardless of the configuration.
+    pub fn check_tls(&self, tls: Option<&TlsConfig>) -> Result<(), Error> {
+        match (self, tls) {
+            #[cfg(unix)]
+            (Listener::Unix(..), Some(_)) => Err(Error::Configuration(
+                "TLS is not supported on Unix sockets".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(unix)]
+    fn from_fd(fd: i32) -> Result<Self, Error> {
+        use std::mem::ManuallyDrop;
+        use std::os::unix::io::FromRawFd;
+        use std::os::unix::net::UnixListener;
+
+        // SAFETY: systemd passes ownership of the file descriptor to this process. The probes are
+        // wrapped in `ManuallyDrop` so that the descriptor is only owned once.
+        unsafe {
+            let tcp = ManuallyDrop::new(TcpListener::from_raw_fd(fd));
+            if tcp.local_addr().is_ok() {
+                return Ok(Listener::Tcp(TcpListener::from_raw_fd(fd)));
+            }
+
+            let unix = ManuallyDrop::new(UnixListener::from_raw_fd(fd));
+            if unix.local_addr().is_ok() {
+                return Ok(Listener::Unix(UnixListener::from_raw_fd(fd), None));
+            }
+        }
+
+        Err(Error::Configuration(
+            "socket passed by systemd is neither a TCP nor a Unix socket".into(),
+        ))
+    }
+
+    #[cfg(not(unix))]
+    fn from_fd(_fd: i32) -> Result<Self, Error> {
+        Err(Error::Configuration(
+            "systemd socket activation is only supported on Unix".into(),
+        ))
+    }
+}
+
+/// First file descriptor that systemd passes to the process
+const SD_LISTEN_FDS_START: i32 = 3;
+
+fn inherited_fds(listen_pid: Option<&str>, listen_fds: Option<&str>, pid: u32) -> usize {
+    let for_this_process =
+        listen_pid.and_then(|listen_pid| listen_pid.parse::<u32>().ok()) == Some(pid);
+
+    if !for_this_process {
+        return 0;
+    }
+
+    listen_fds
+        .and_then(|listen_fds| listen_fds.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs::{
+        remove_dir_all, remove_file, rename, set_permissions, symlink_metadata, DirBuilder,
+        Permissions,
+    };
+    use std::io;
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use hyper::server::accept::Accept;
+
+    use crate::config::UnixSocketConfig;
+    use crate::Error;
+
+    use super::Listener;
+
+    impl Listener {
+        /// Binds a Unix socket, and removes a stale socket file that was left behind
+        pub fn bind_unix(config: &UnixSocketConfig) -> Result<Self, Error> {
+            let path = &config.path;
+
+            if let Ok(metadata) = symlink_metadata(path) {
+                if !metadata.file_type().is_socket() {
+                    return Err(Error::Configuration(format!(
+                        "{} already exists and is not a socket",
+                        path.display()
+                    )));
+                }
+
+                if UnixStream::connect(path).is_ok() {
+                    return Err(Error::Configuration(format!(
+                        "{} is already in use by another process",
+                        path.display()
+                    )));
+                }
+
+                remove_file(path).map_err(|error| {
+                    Error::Configuration(format!(
+                        "failed to remove stale socket {}: {}",
+                        path.display(),
+                        error
+                    ))
+                })?;
+            }
+
+            let listener = match config.mode {
+                Some(mode) => bind_with_mode(path, mode),
+                None => UnixListener::bind(path),
+            }
+            .map_err(|error| {
+                Error::Configuration(format!("failed to bind {}: {}", path.display(), error))
+            })?;
+
+            Ok(Listener::Unix(listener, Some(path.clone())))
+        }
+    }
+
+    /// Binds the socket in a private directory, and moves it into place once its permissions have
+    /// been set, so that it is never exposed with the default permissions
+    fn bind_with_mode(path: &Path, mode: u32) -> io::Result<UnixListener> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+        let parent = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+
+        let private = parent.join(format!(
+            ".{}.{}",
+            file_name.to_string_lossy(),
+            std::process::id()
+        ));
+        DirBuilder::new().mode(0o700).create(&private)?;
+
+        let temporary = private.join("socket");
+        let result = UnixListener::bind(&temporary).and_then(|listener| {
+            set_permissions(&temporary, Permissions::from_mode(mode))?;
+            rename(&temporary, path)?;
+            Ok(listener)
+        });
+
+        remove_dir_all(&private).ok();
+
+        result
+    }
+
+    /// Accepts connections on a Unix socket for hyper
+    #[derive(Debug)]
+    pub(crate) struct UnixAccept(tokio::net::UnixListener);
+
+    impl UnixAccept {
+        pub fn new(listener: UnixListener) -> io::Result<Self> {
+            listener.set_nonblocking(true)?;
+            tokio::net::UnixListener::from_std(listener).map(Self)
+        }
+    }
+
+    impl Accept for UnixAccept {
+        type Conn = tokio::net::UnixStream;
+        type Error = io::Error;
+
+        fn poll_accept(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+            self.0
+                .poll_accept(cx)
+                .map(|result| Some(result.map(|(stream, _)| stream)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inherited_fds, Listener};
+
+    #[test]
+    fn inherited_fds_returns_count_for_this_process() {
+        assert_eq!(2, inherited_fds(Some("42"), Some("2"), 42));
+    }
+
+    #[test]
+    fn inherited_fds_ignores_other_processes() {
+        assert_eq!(0, inherited_fds(Some("41"), Some("2"), 42));
+        assert_eq!(0, inherited_fds(None, Some("2"), 42));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_unix_sets_mode_before_moving_socket_into_place() {
+        use std::os::unix::fs::PermissionsExt;
+
+        use crate::config::UnixSocketConfig;
+
+        let directory = std::env::temp_dir().join("octox-listener-mode");
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("octox.sock");
+
+        let listener = Listener::bind_unix(&UnixSocketConfig {
+            path: path.clone(),
+            mode: Some(0o600),
+        })
+        .unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+        assert!(matches!(listener, Listener::Unix(_, Some(_))));
+
+        // Only the socket is left, the private directory has been removed
+        assert_eq!(1, std::fs::read_dir(&directory).unwrap().count());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_tls_rejects_unix_socket() {
+        use std::os::unix::net::UnixListener;
+
+        use crate::config::TlsConfig;
+
+        let path = std::env::temp_dir().join("octox-listener-tls.sock");
+        std::fs::remove_file(&path).ok();
+        let listener = Listener::Unix(UnixListener::bind(&path).unwrap(), Some(path));
+        let tls = TlsConfig {
+            certificate_path: "cert.pem".into(),
+            key_path: "key.pem".into(),
+            redirect_address: None,
+        };
+
+        assert!(listener.check_tls(Some(&tls)).is_err());
+        assert!(listener.check_tls(None).is_ok());
+    }
+}
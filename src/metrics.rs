@@ -0,0 +1,411 @@
+//! Prometheus metrics
+//!
+//! The metrics are collected in a process-wide registry, and are rendered in Prometheus' text
+//! exposition format by the metrics route. They require the `metrics` feature.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+
+use crate::auth::AuthError;
+use crate::WorkflowError;
+
+/// Upper bounds of the buckets of the duration histograms in seconds
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    deliveries: Family<u64>,
+    signature_failures: Family<u64>,
+    workflow_duration: Family<Histogram>,
+    step_duration: Family<Histogram>,
+    workflow_errors: Family<u64>,
+    token_refreshes: Family<u64>,
+    github_request_duration: Family<Histogram>,
+    github_rate_limit: Family<f64>,
+    github_rate_limit_remaining: Family<f64>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            deliveries: Family::new(
+                "octox_deliveries_total",
+                "Webhook deliveries by event, action and result",
+                &["event", "action", "result"],
+            ),
+            signature_failures: Family::new(
+                "octox_signature_failures_total",
+                "Webhook deliveries that failed signature verification",
+                &["reason"],
+            ),
+            workflow_duration: Family::new(
+                "octox_workflow_duration_seconds",
+                "Time it took to execute a workflow",
+                &["event"],
+            ),
+            step_duration: Family::new(
+                "octox_step_duration_seconds",
+                "Time it took to execute a workflow step",
+                &["step"],
+            ),
+            workflow_errors: Family::new(
+                "octox_workflow_errors_total",
+                "Workflows that failed with an error",
+                &["error"],
+            ),
+            token_refreshes: Family::new(
+                "octox_token_refreshes_total",
+                "Installation tokens that were requested from GitHub",
+                &["result"],
+            ),
+            github_request_duration: Family::new(
+                "octox_github_request_duration_seconds",
+                "Time it took GitHub to respond to a request",
+                &["method", "status"],
+            ),
+            github_rate_limit: Family::new(
+                "octox_github_rate_limit",
+                "Number of requests that are allowed in the current rate limit window",
+                &["resource"],
+            ),
+            github_rate_limit_remaining: Family::new(
+                "octox_github_rate_limit_remaining",
+                "Number of requests that remain in the current rate limit window",
+                &["resource"],
+            ),
+        }
+    }
+
+    /// Returns the process-wide registry
+    pub fn global() -> &'static Self {
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        METRICS.get_or_init(Metrics::new)
+    }
+
+    /// Records a delivery that was refused because the server is shutting down
+    ///
+    /// Refused deliveries are not parsed, so their action is unknown.
+    pub fn record_unavailable(&self, event: &str) {
+        self.deliveries.inc(&[event, "", "unavailable"]);
+    }
+
+    /// Records a delivery whose workflow has been executed
+    pub fn record_workflow<T>(
+        &self,
+        event: &str,
+        action: Option<&str>,
+        result: &Result<T, WorkflowError>,
+        duration: Duration,
+    ) {
+        let label = match result {
+            Ok(_) => "success",
+            Err(WorkflowError::Skipped(_)) => "skipped",
+            Err(WorkflowError::Rejected(_)) => "rejected",
+            Err(WorkflowError::RetryLater(_)) => "retry_later",
+            Err(error) => {
+                self.workflow_errors.inc(&[error.code()]);
+                "error"
+            }
+        };
+
+        self.deliveries
+            .inc(&[event, action.unwrap_or_default(), label]);
+        self.workflow_duration.observe(&[event], duration);
+    }
+
+    pub fn record_step(&self, step: &str, duration: Duration) {
+        self.step_duration.observe(&[step], duration);
+    }
+
+    pub fn record_signature_failure(&self, error: &AuthError) {
+        self.signature_failures.inc(&[error.code()]);
+    }
+
+    pub fn record_token_refresh(&self, success: bool) {
+        self.token_refreshes
+            .inc(&[if success { "success" } else { "error" }]);
+    }
+
+    /// Records the latency of a request to GitHub, and the rate limit from the response's headers
+    ///
+    /// Requests that failed before GitHub responded have no status code.
+    pub fn record_github_request(
+        &self,
+        method: &Method,
+        response: Option<(StatusCode, &HeaderMap)>,
+        duration: Duration,
+    ) {
+        let status = response.map(|(status, _)| status.as_u16().to_string());
+        self.github_request_duration.observe(
+            &[method.as_str(), status.as_deref().unwrap_or("error")],
+            duration,
+        );
+
+        if let Some((_, headers)) = response {
+            let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+            let resource = header("x-ratelimit-resource").unwrap_or("core");
+
+            if let Some(limit) = header("x-ratelimit-limit").and_then(|value| value.parse().ok()) {
+                self.github_rate_limit.set(&[resource], limit);
+            }
+            if let Some(remaining) =
+                header("x-ratelimit-remaining").and_then(|value| value.parse().ok())
+            {
+                self.github_rate_limit_remaining.set(&[resource], remaining);
+            }
+        }
+    }
+
+    /// Renders the metrics in Prometheus' text exposition format
+    pub fn render(&self, queue_depth: usize) -> String {
+        let mut output = String::new();
+
+        self.deliveries.render(&mut output);
+        self.signature_failures.render(&mut output);
+        self.workflow_duration.render(&mut output);
+        self.step_duration.render(&mut output);
+        self.workflow_errors.render(&mut output);
+        self.token_refreshes.render(&mut output);
+        self.github_request_duration.render(&mut output);
+        self.github_rate_limit.render(&mut output);
+        self.github_rate_limit_remaining.render(&mut output);
+
+        let _ = writeln!(
+            output,
+            "# HELP octox_queue_depth Webhook deliveries that are being processed\n\
+             # TYPE octox_queue_depth gauge\n\
+             octox_queue_depth {}",
+            queue_depth
+        );
+
+        output
+    }
+}
+
+/// Metric with one time series per combination of label values
+#[derive(Debug)]
+struct Family<T> {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    series: Mutex<BTreeMap<Vec<String>, T>>,
+}
+
+impl<T: Series> Family<T> {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn with_series(&self, values: &[&str], f: impl FnOnce(&mut T)) {
+        let key = values.iter().map(|value| value.to_string()).collect();
+        f(self.series.lock().entry(key).or_default());
+    }
+
+    fn render(&self, output: &mut String) {
+        let series = self.series.lock();
+
+        if series.is_empty() {
+            return;
+        }
+
+        let _ = writeln!(output, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(output, "# TYPE {} {}", self.name, T::TYPE);
+
+        for (values, series) in series.iter() {
+            let labels: Vec<(&str, &str)> = self
+                .labels
+                .iter()
+                .copied()
+                .zip(values.iter().map(String::as_str))
+                .collect();
+
+            series.render(self.name, &labels, output);
+        }
+    }
+}
+
+impl Family<u64> {
+    fn inc(&self, values: &[&str]) {
+        self.with_series(values, |count| *count += 1);
+    }
+}
+
+impl Family<f64> {
+    fn set(&self, values: &[&str], value: f64) {
+        self.with_series(values, |gauge| *gauge = value);
+    }
+}
+
+impl Family<Histogram> {
+    fn observe(&self, values: &[&str], duration: Duration) {
+        self.with_series(values, |histogram| {
+            histogram.observe(duration.as_secs_f64())
+        });
+    }
+}
+
+trait Series: Default {
+    const TYPE: &'static str;
+
+    fn render(&self, name: &str, labels: &[(&str, &str)], output: &mut String);
+}
+
+impl Series for u64 {
+    const TYPE: &'static str = "counter";
+
+    fn render(&self, name: &str, labels: &[(&str, &str)], output: &mut String) {
+        let _ = writeln!(output, "{}{} {}", name, format_labels(labels), self);
+    }
+}
+
+impl Series for f64 {
+    const TYPE: &'static str = "gauge";
+
+    fn render(&self, name: &str, labels: &[(&str, &str)], output: &mut String) {
+        let _ = writeln!(output, "{}{} {}", name, format_labels(labels), self);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, upper_bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if value <= upper_bound {
+                *bucket += 1;
+            }
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+impl Series for Histogram {
+    const TYPE: &'static str = "histogram";
+
+    fn render(&self, name: &str, labels: &[(&str, &str)], output: &mut String) {
+        let buckets = DURATION_BUCKETS
+            .iter()
+            .map(|upper_bound| upper_bound.to_string())
+            .zip(self.buckets)
+            .chain([("+Inf".to_string(), self.count)]);
+
+        for (upper_bound, count) in buckets {
+            let mut labels = labels.to_vec();
+            labels.push(("le", &upper_bound));
+
+            let _ = writeln!(
+                output,
+                "{}_bucket{} {}",
+                name,
+                format_labels(&labels),
+                count
+            );
+        }
+
+        let labels = format_labels(labels);
+        let _ = writeln!(output, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(output, "{}_count{} {}", name, labels, self.count);
+    }
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+
+    format!("{{{}}}", labels.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_labels, Family, Histogram};
+
+    #[test]
+    fn counter_renders_series_per_label_value() {
+        let family = Family::<u64>::new("deliveries_total", "Deliveries", &["event"]);
+
+        family.inc(&["check_run"]);
+        family.inc(&["check_run"]);
+        family.inc(&["ping"]);
+
+        let mut output = String::new();
+        family.render(&mut output);
+
+        assert_eq!(
+            "# HELP deliveries_total Deliveries\n\
+             # TYPE deliveries_total counter\n\
+             deliveries_total{event=\"check_run\"} 2\n\
+             deliveries_total{event=\"ping\"} 1\n",
+            output
+        );
+    }
+
+    #[test]
+    fn family_without_series_renders_nothing() {
+        let family = Family::<u64>::new("deliveries_total", "Deliveries", &["event"]);
+
+        let mut output = String::new();
+        family.render(&mut output);
+
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn histogram_counts_values_in_cumulative_buckets() {
+        let family = Family::<Histogram>::new("duration_seconds", "Duration", &[]);
+
+        family.observe(&[], Duration::from_millis(20));
+        family.observe(&[], Duration::from_secs(20));
+
+        let mut output = String::new();
+        family.render(&mut output);
+
+        assert!(output.contains("duration_seconds_bucket{le=\"0.01\"} 0\n"));
+        assert!(output.contains("duration_seconds_bucket{le=\"0.025\"} 1\n"));
+        assert!(output.contains("duration_seconds_bucket{le=\"10\"} 1\n"));
+        assert!(output.contains("duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(output.contains("duration_seconds_count 2\n"));
+    }
+
+    #[test]
+    fn format_labels_escapes_values() {
+        assert_eq!(
+            "{step=\"a\\\"b\\\\c\"}",
+            format_labels(&[("step", "a\"b\\c")])
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+use crate::config::Environment;
+use crate::Error;
+
+/// Returns the response to a request that failed with the error
+///
+/// Errors with a client or server error status are described by a [`Problem`]. Workflows can also
+/// acknowledge a delivery without processing it, e.g. when they skip it, which is not a failure
+/// and is described by an [`Outcome`] instead.
+pub(crate) fn error_response(
+    error: &Error,
+    environment: Environment,
+    delivery_id: Option<String>,
+) -> Response {
+    if error.status().is_success() {
+        Outcome::new(error)
+            .with_delivery_id(delivery_id)
+            .into_response()
+    } else {
+        Problem::new(error, environment)
+            .with_delivery_id(delivery_id)
+            .into_response()
+    }
+}
+
+/// Error response in the format of RFC 7807, `application/problem+json`
+///
+/// Besides the standard members, the response contains a stable `code` for the error, and the id
+/// of the webhook delivery that caused it. The details of server errors are only included in the
+/// development environment, since they might reveal internal information.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub(crate) struct Problem {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: String,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery_id: Option<String>,
+}
+
+impl Problem {
+    pub fn new(error: &Error, environment: Environment) -> Self {
+        let status = error.status();
+        let detail = if status.is_server_error() && environment == Environment::Production {
+            None
+        } else {
+            Some(error.to_string())
+        };
+
+        Self {
+            kind: "about:blank",
+            title: status
+                .canonical_reason()
+                .unwrap_or_else(|| status.as_str())
+                .into(),
+            status: status.as_u16(),
+            detail,
+            code: error.code(),
+            delivery_id: None,
+        }
+    }
+
+    pub fn with_delivery_id(mut self, delivery_id: Option<String>) -> Self {
+        self.delivery_id = delivery_id;
+        self
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        (
+            status,
+            [(CONTENT_TYPE, "application/problem+json")],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+/// Problem document with the details of a server error, attached to responses that hide them
+#[derive(Clone, Debug)]
+pub(crate) struct DevelopmentProblem(pub Problem);
+
+/// Middleware that shows the details of server errors in the development environment
+///
+/// Errors that are turned into responses outside of the webhook route, e.g. by custom routes, do
+/// not know the environment. They attach a [`DevelopmentProblem`], which replaces the response in
+/// development.
+pub(crate) async fn show_problem_details(
+    request: Request<Body>,
+    next: Next<Body>,
+    environment: Environment,
+) -> Response {
+    let mut response = next.run(request).await;
+
+    match response.extensions_mut().remove::<DevelopmentProblem>() {
+        Some(DevelopmentProblem(problem)) if environment == Environment::Development => {
+            problem.into_response()
+        }
+        _ => response,
+    }
+}
+
+/// Response to a delivery that was acknowledged without being processed, `application/json`
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub(crate) struct Outcome {
+    #[serde(skip)]
+    status: StatusCode,
+    code: &'static str,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delivery_id: Option<String>,
+}
+
+impl Outcome {
+    pub fn new(error: &Error) -> Self {
+        Self {
+            status: error.status(),
+            code: error.code(),
+            detail: error.to_string(),
+            delivery_id: None,
+        }
+    }
+
+    pub fn with_delivery_id(mut self, delivery_id: Option<String>) -> Self {
+        self.delivery_id = delivery_id;
+        self
+    }
+}
+
+impl IntoResponse for Outcome {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use axum::http::header::CONTENT_TYPE;
+
+    use crate::config::Environment;
+    use crate::{Error, WorkflowError};
+
+    use super::{error_response, Problem};
+
+    #[test]
+    fn new_hides_details_of_server_errors_in_production() {
+        let error = Error::UnexpectedError(anyhow!("connection to 10.0.0.1 refused"));
+
+        let problem = Problem::new(&error, Environment::Production);
+
+        assert_eq!(500, problem.status);
+        assert_eq!("unexpected_error", problem.code);
+        assert_eq!(None, problem.detail);
+    }
+
+    #[test]
+    fn new_shows_details_of_server_errors_in_development() {
+        let error = Error::UnexpectedError(anyhow!("connection to 10.0.0.1 refused"));
+
+        let problem = Problem::new(&error, Environment::Development);
+
+        assert_eq!(
+            Some("connection to 10.0.0.1 refused"),
+            problem.detail.as_deref()
+        );
+    }
+
+    #[test]
+    fn new_shows_details_of_client_errors() {
+        let error = Error::Workflow(WorkflowError::MissingData("missing check run".into()));
+
+        let problem = Problem::new(&error, Environment::Production);
+
+        assert_eq!(400, problem.status);
+        assert_eq!("Bad Request", problem.title);
+        assert_eq!(Some("missing check run"), problem.detail.as_deref());
+    }
+
+    #[test]
+    fn error_response_describes_acknowledged_deliveries_as_json() {
+        let error = Error::Workflow(WorkflowError::Skipped("check run is not ours".into()));
+
+        let response = error_response(&error, Environment::Production, None);
+
+        assert_eq!(202, response.status().as_u16());
+        assert_eq!("application/json", response.headers()[CONTENT_TYPE]);
+    }
+
+    #[test]
+    fn error_response_describes_failures_as_problem() {
+        let error = Error::Workflow(WorkflowError::Rejected("check run is closed".into()));
+
+        let response = error_response(&error, Environment::Production, None);
+
+        assert_eq!(422, response.status().as_u16());
+        assert_eq!("application/problem+json", response.headers()[CONTENT_TYPE]);
+    }
+}
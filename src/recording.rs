@@ -0,0 +1,345 @@
+//! Recording and replay of webhook deliveries
+//!
+//! A [`Recorder`] that is registered with [`Octox::record_deliveries`](crate::Octox::record_deliveries)
+//! writes the headers and raw body of every delivery that the webhook route accepts to a
+//! directory, one JSON file per delivery. Deliveries with an invalid signature are not recorded.
+//! Headers and payload fields that contain sensitive data can be redacted before they are written.
+//!
+//! The recorded deliveries can later be replayed with [`Replay`], either against a running server
+//! or in-process against the app's [`Router`]. Since the recordings do not contain the original
+//! signatures, a replay either signs each delivery again with the webhook secret, or sends it
+//! unsigned to a server that skips signature verification in development, see
+//! [`Octox::skip_signature_verification`](crate::Octox::skip_signature_verification).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, Request};
+use axum::response::Response;
+use axum::Router;
+use chrono::Utc;
+use github_parts::github::WebhookSecret;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use crate::auth::sign;
+use crate::Error;
+
+/// Headers that are never recorded, since they are either secret or recomputed on replay
+const UNRECORDED_HEADERS: [&str; 5] = [
+    "authorization",
+    "cookie",
+    "x-hub-signature",
+    "x-hub-signature-256",
+    "content-length",
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Writes incoming webhook deliveries to a directory
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    directory: PathBuf,
+    redacted_headers: Vec<String>,
+    redacted_fields: Vec<String>,
+}
+
+/// Webhook delivery as it was received by the webhook route
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct RecordedDelivery {
+    /// Time at which the delivery was received, in RFC 3339 format
+    pub recorded_at: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl Recorder {
+    /// Creates a recorder that writes deliveries to the directory, which is created if necessary
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            redacted_headers: Vec::new(),
+            redacted_fields: Vec::new(),
+        }
+    }
+
+    /// Omits the header from recordings
+    ///
+    /// Signatures, `Authorization` and `Cookie` headers are never recorded.
+    pub fn redact_header(mut self, name: &str) -> Self {
+        self.redacted_headers.push(name.to_lowercase());
+        self
+    }
+
+    /// Replaces a field in the payload with `[REDACTED]`
+    ///
+    /// The field is given as a JSON pointer, e.g. `/sender/email`. Redacting a field changes the
+    /// payload, so it no longer matches GitHub's original signature.
+    pub fn redact_field(mut self, pointer: &str) -> Self {
+        self.redacted_fields.push(pointer.into());
+        self
+    }
+
+    /// Writes the delivery to the directory, and returns the path of the recording
+    pub fn record(&self, headers: &HeaderMap, body: &[u8]) -> Result<PathBuf, Error> {
+        let delivery = self.redact(headers, body);
+
+        // The delivery id is sent by the client, so it must not be trusted as a file name
+        let delivery_id: String = delivery
+            .delivery_id()
+            .unwrap_or("unknown")
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect();
+        let name = format!(
+            "{}-{}.json",
+            Utc::now().format("%Y%m%dT%H%M%S%.6fZ"),
+            delivery_id
+        );
+        let path = self.directory.join(name);
+
+        fs::create_dir_all(&self.directory).with_context(|| {
+            format!(
+                "failed to create recording directory {}",
+                self.directory.display()
+            )
+        })?;
+        fs::write(&path, serde_json::to_vec_pretty(&delivery)?)
+            .with_context(|| format!("failed to write recording {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    fn redact(&self, headers: &HeaderMap, body: &[u8]) -> RecordedDelivery {
+        let headers = headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.as_str();
+                !UNRECORDED_HEADERS.contains(&name)
+                    && !self.redacted_headers.iter().any(|h| h == name)
+            })
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.into())))
+            .collect();
+
+        let body = match serde_json::from_slice::<Value>(body) {
+            Ok(mut payload) if !self.redacted_fields.is_empty() => {
+                for pointer in &self.redacted_fields {
+                    if let Some(field) = payload.pointer_mut(pointer) {
+                        *field = Value::String(REDACTED.into());
+                    }
+                }
+
+                payload.to_string()
+            }
+            _ => String::from_utf8_lossy(body).into(),
+        };
+
+        RecordedDelivery {
+            recorded_at: Utc::now().to_rfc3339(),
+            headers,
+            body,
+        }
+    }
+}
+
+impl RecordedDelivery {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let content = fs::read(path)
+            .with_context(|| format!("failed to read recording {}", path.display()))?;
+
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Loads all recordings in the directory, in the order in which they were recorded
+    pub fn load_all(directory: impl AsRef<Path>) -> Result<Vec<Self>, Error> {
+        let directory = directory.as_ref();
+        let entries = fs::read_dir(directory).with_context(|| {
+            format!("failed to read recording directory {}", directory.display())
+        })?;
+
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry.context("failed to read recording directory")?.path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        paths.iter().map(Self::load).collect()
+    }
+
+    pub fn delivery_id(&self) -> Option<&str> {
+        self.header("x-github-delivery")
+    }
+
+    pub fn event(&self) -> Option<&str> {
+        self.header("x-github-event")
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+/// Sends recorded deliveries to an octox app
+#[derive(Clone, Debug)]
+pub struct Replay {
+    webhook_secret: Option<WebhookSecret>,
+}
+
+impl Replay {
+    /// Signs each delivery with the webhook secret of the app that it is replayed against
+    pub fn signed(webhook_secret: WebhookSecret) -> Self {
+        Self {
+            webhook_secret: Some(webhook_secret),
+        }
+    }
+
+    /// Sends deliveries without a signature, which requires that the app skips verification
+    pub fn unsigned() -> Self {
+        Self {
+            webhook_secret: None,
+        }
+    }
+
+    /// Builds the webhook request for the delivery
+    pub fn request(&self, delivery: &RecordedDelivery, uri: &str) -> Result<Request<Body>, Error> {
+        let mut request = Request::post(uri);
+
+        for (name, value) in &delivery.headers {
+            if name != "host" && name != "transfer-encoding" {
+                request = request.header(name, value);
+            }
+        }
+
+        if let Some(webhook_secret) = &self.webhook_secret {
+            request = request.header(
+                "X-Hub-Signature-256",
+                sign(delivery.body.as_bytes(), webhook_secret)?,
+            );
+        }
+
+        let request = request
+            .body(Body::from(delivery.body.clone()))
+            .context("failed to build webhook request")?;
+
+        Ok(request)
+    }
+
+    /// Sends the delivery to the webhook route of a running server, e.g. `http://localhost:3000/`
+    pub async fn send(
+        &self,
+        url: &str,
+        delivery: &RecordedDelivery,
+    ) -> Result<reqwest::Response, Error> {
+        let request = self.request(delivery, url)?;
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body)
+            .await
+            .context("failed to read webhook request body")?;
+
+        let response = reqwest::Client::new()
+            .post(url)
+            .headers(parts.headers)
+            .body(body)
+            .send()
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Sends the delivery to the router of an app in the same process
+    ///
+    /// The path is the path of the webhook route inside the router, e.g. `/`.
+    pub async fn send_to_router(
+        &self,
+        router: &Router,
+        path: &str,
+        delivery: &RecordedDelivery,
+    ) -> Result<Response, Error> {
+        let request = self.request(delivery, path)?;
+
+        match router.clone().oneshot(request).await {
+            Ok(response) => Ok(response),
+            Err(error) => match error {},
+        }
+    }
+}
+
+/// Records a delivery that the webhook route has accepted
+///
+/// The route only records deliveries whose signatures it has verified, so that unauthenticated
+/// requests cannot fill the directory. The recording is written on the blocking thread pool, and
+/// failing to record a delivery does not affect its processing.
+pub(crate) async fn record_delivery(recorder: Arc<Recorder>, headers: HeaderMap, body: Bytes) {
+    match tokio::task::spawn_blocking(move || recorder.record(&headers, &body)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(error)) => tracing::warn!(%error, "failed to record webhook delivery"),
+        Err(error) => tracing::warn!(%error, "failed to record webhook delivery"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+    use serde_json::{json, Value};
+
+    use super::Recorder;
+
+    fn headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-GitHub-Event", "check_run".parse().unwrap());
+        headers.insert("X-GitHub-Delivery", "1".parse().unwrap());
+        headers.insert("X-Hub-Signature-256", "sha256=0".parse().unwrap());
+        headers.insert("X-Forwarded-For", "127.0.0.1".parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn redact_omits_signatures_and_redacted_headers() {
+        let recorder = Recorder::new("recordings").redact_header("X-Forwarded-For");
+
+        let delivery = recorder.redact(&headers(), b"{}");
+
+        assert_eq!(
+            vec!["x-github-delivery", "x-github-event"],
+            delivery.headers.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("1"), delivery.delivery_id());
+    }
+
+    #[test]
+    fn redact_replaces_redacted_fields() {
+        let recorder = Recorder::new("recordings")
+            .redact_field("/sender/login")
+            .redact_field("/missing");
+        let body = json!({ "action": "created", "sender": { "login": "octocat" } }).to_string();
+
+        let delivery = recorder.redact(&headers(), body.as_bytes());
+
+        assert_eq!(
+            json!({ "action": "created", "sender": { "login": "[REDACTED]" } }),
+            serde_json::from_str::<Value>(&delivery.body).unwrap()
+        );
+    }
+
+    #[test]
+    fn redact_keeps_raw_body_without_redacted_fields() {
+        let body = "{ \"action\":  \"created\" }";
+
+        let delivery = Recorder::new("recordings").redact(&headers(), body.as_bytes());
+
+        assert_eq!(body, delivery.body);
+    }
+}
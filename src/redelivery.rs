@@ -0,0 +1,315 @@
+//! Redelivery of webhooks that failed
+//!
+//! GitHub records webhook deliveries that the app failed to process, e.g. because it was down, but
+//! it does not retry them. [`Redelivery`] lists the app's recent deliveries through the hook
+//! deliveries API, and asks GitHub to redeliver the ones that failed. It can run once, e.g. from a
+//! command or after a deployment, or periodically with [`Redelivery::run_every`].
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::client::{GitHubClient, GitHubError};
+
+/// GitHub only redelivers deliveries from the past three days
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+const DEFAULT_MAX_REDELIVERIES: usize = 50;
+const DEFAULT_MAX_ATTEMPTS: usize = 3;
+const DEFAULT_DELAY: Duration = Duration::from_secs(1);
+
+/// Task that redelivers failed webhook deliveries
+///
+/// Each run scans the deliveries since the previous run, and requests a redelivery for every
+/// delivery whose attempts have all failed. The redeliveries are limited in number per run, are
+/// spaced out by a delay, and are given up after a number of attempts. Deliveries that could not be
+/// redelivered because of the limits are picked up by the next run.
+///
+/// The attempts are counted by the task and from the redeliveries that GitHub lists for the
+/// delivery, so redeliveries requested by another instance count towards the limit as well.
+///
+/// The client must be authenticated as the app, not as one of its installations.
+#[derive(Debug)]
+pub struct Redelivery {
+    github_client: GitHubClient,
+    max_age: Duration,
+    max_redeliveries: usize,
+    max_attempts: usize,
+    delay: Duration,
+    checkpoint: Option<DateTime<Utc>>,
+    requested: HashMap<String, (usize, DateTime<Utc>)>,
+}
+
+/// Outcome of a redelivery run
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RedeliveryReport {
+    /// Number of deliveries that have been scanned
+    pub scanned: usize,
+    /// GUIDs of the deliveries that have been redelivered
+    pub redelivered: Vec<String>,
+    /// GUIDs of the deliveries for which the redelivery request failed
+    pub failed: Vec<String>,
+    /// Number of failed deliveries that exceeded the limit of redeliveries per run
+    pub deferred: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct HookDelivery {
+    id: u64,
+    guid: String,
+    delivered_at: String,
+    #[serde(default)]
+    redelivery: bool,
+    status_code: u16,
+}
+
+impl HookDelivery {
+    fn delivered_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.delivered_at)
+            .ok()
+            .map(|delivered_at| delivered_at.with_timezone(&Utc))
+    }
+
+    fn failed(&self) -> bool {
+        is_failed_status(self.status_code)
+    }
+}
+
+impl Redelivery {
+    pub fn new(github_client: GitHubClient) -> Self {
+        Self {
+            github_client,
+            max_age: DEFAULT_MAX_AGE,
+            max_redeliveries: DEFAULT_MAX_REDELIVERIES,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            delay: DEFAULT_DELAY,
+            checkpoint: None,
+            requested: HashMap::new(),
+        }
+    }
+
+    /// Sets how far back deliveries are redelivered, which defaults to three days
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets how many deliveries are redelivered per run, which defaults to 50
+    pub fn max_redeliveries(mut self, max_redeliveries: usize) -> Self {
+        self.max_redeliveries = max_redeliveries;
+        self
+    }
+
+    /// Sets how often a delivery is attempted before it is given up, which defaults to 3
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the time between two redelivery requests, which defaults to one second
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Redelivers the deliveries that failed since the previous run
+    pub async fn run(&mut self) -> Result<RedeliveryReport, GitHubError> {
+        let now = Utc::now();
+        let oldest = now
+            - chrono::Duration::from_std(self.max_age).unwrap_or_else(|_| chrono::Duration::zero());
+        let since = self
+            .checkpoint
+            .map_or(oldest, |checkpoint| checkpoint.max(oldest));
+
+        // Deliveries are listed from newest to oldest
+        let deliveries: Vec<HookDelivery> = self
+            .github_client
+            .paginate::<HookDelivery>("app/hook/deliveries?per_page=100")
+            .try_take_while(|delivery| {
+                let recent = delivery
+                    .delivered_at()
+                    .is_none_or(|delivered_at| delivered_at >= since);
+
+                futures::future::ready(Ok(recent))
+            })
+            .try_collect()
+            .await?;
+
+        self.requested
+            .retain(|_, (_, requested_at)| *requested_at >= oldest);
+
+        let mut report = RedeliveryReport {
+            scanned: deliveries.len(),
+            ..Default::default()
+        };
+
+        let listed_attempts = redelivery_attempts(&deliveries);
+
+        for delivery in failed_deliveries(&deliveries) {
+            let requested = self
+                .requested
+                .get(&delivery.guid)
+                .map_or(0, |(attempts, _)| *attempts);
+            let listed = listed_attempts
+                .get(delivery.guid.as_str())
+                .copied()
+                .unwrap_or(0);
+            let attempts = requested.max(listed);
+
+            if attempts >= self.max_attempts {
+                continue;
+            }
+
+            if report.redelivered.len() + report.failed.len() >= self.max_redeliveries {
+                report.deferred += 1;
+                continue;
+            }
+
+            if !report.redelivered.is_empty() || !report.failed.is_empty() {
+                tokio::time::sleep(self.delay).await;
+            }
+
+            let path = format!("app/hook/deliveries/{}/attempts", delivery.id);
+            match self
+                .github_client
+                .request::<(), Value>(Method::POST, &path, None)
+                .await
+            {
+                Ok(_) => {
+                    self.requested
+                        .insert(delivery.guid.clone(), (attempts + 1, Utc::now()));
+                    report.redelivered.push(delivery.guid.clone());
+                }
+                Err(error) => {
+                    tracing::warn!(%error, guid = %delivery.guid, "failed to request redelivery");
+                    report.failed.push(delivery.guid.clone());
+                }
+            }
+        }
+
+        // Deliveries that were not redelivered are scanned again in the next run
+        if report.deferred == 0 && report.failed.is_empty() {
+            self.checkpoint = deliveries
+                .iter()
+                .filter_map(HookDelivery::delivered_at)
+                .max()
+                .or(self.checkpoint);
+        }
+
+        Ok(report)
+    }
+
+    /// Runs the redelivery periodically, starting immediately
+    pub async fn run_every(mut self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            match self.run().await {
+                Ok(report) if !report.redelivered.is_empty() || report.deferred > 0 => {
+                    tracing::info!(
+                        redelivered = report.redelivered.len(),
+                        failed = report.failed.len(),
+                        deferred = report.deferred,
+                        "redelivered failed webhook deliveries"
+                    );
+                }
+                Ok(_) => {}
+                Err(error) => tracing::error!(%error, "failed to redeliver webhook deliveries"),
+            }
+        }
+    }
+}
+
+/// Whether a delivery attempt with the status code failed in a way that a redelivery can fix
+///
+/// GitHub records a status code of 0 when the request timed out or the connection failed. Client
+/// errors would fail again, so only those and server errors count as failed.
+pub fn is_failed_status(status_code: u16) -> bool {
+    status_code == 0 || status_code >= 500
+}
+
+/// Returns the latest attempt of each delivery whose attempts have all failed, oldest first
+fn failed_deliveries(deliveries: &[HookDelivery]) -> Vec<&HookDelivery> {
+    let mut latest_attempts: Vec<&HookDelivery> = Vec::new();
+    let mut settled: HashMap<&str, bool> = HashMap::new();
+
+    for delivery in deliveries {
+        let entry = settled.entry(&delivery.guid).or_insert_with(|| {
+            latest_attempts.push(delivery);
+            false
+        });
+        *entry |= !delivery.failed();
+    }
+
+    latest_attempts
+        .into_iter()
+        .rev()
+        .filter(|delivery| !settled[delivery.guid.as_str()])
+        .collect()
+}
+
+/// Counts the redeliveries that GitHub lists for each delivery
+fn redelivery_attempts(deliveries: &[HookDelivery]) -> HashMap<&str, usize> {
+    let mut attempts = HashMap::new();
+
+    for delivery in deliveries.iter().filter(|delivery| delivery.redelivery) {
+        *attempts.entry(delivery.guid.as_str()).or_insert(0) += 1;
+    }
+
+    attempts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{failed_deliveries, HookDelivery};
+
+    fn delivery(id: u64, guid: &str, status_code: u16) -> HookDelivery {
+        HookDelivery {
+            id,
+            guid: guid.into(),
+            delivered_at: "2022-06-01T00:00:00Z".into(),
+            redelivery: false,
+            status_code,
+        }
+    }
+
+    #[test]
+    fn failed_deliveries_ignores_deliveries_that_succeeded_or_were_rejected() {
+        let deliveries = [
+            delivery(4, "c", 422),
+            delivery(3, "a", 200),
+            delivery(2, "b", 500),
+            delivery(1, "a", 502),
+        ];
+
+        let failed: Vec<u64> = failed_deliveries(&deliveries)
+            .iter()
+            .map(|delivery| delivery.id)
+            .collect();
+
+        assert_eq!(vec![2], failed);
+    }
+
+    #[test]
+    fn failed_deliveries_returns_latest_attempt_oldest_first() {
+        let deliveries = [
+            delivery(4, "b", 0),
+            delivery(3, "a", 503),
+            delivery(2, "b", 500),
+            delivery(1, "a", 500),
+        ];
+
+        let failed: Vec<u64> = failed_deliveries(&deliveries)
+            .iter()
+            .map(|delivery| delivery.id)
+            .collect();
+
+        assert_eq!(vec![3, 4], failed);
+    }
+}
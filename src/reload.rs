@@ -0,0 +1,288 @@
+use std::fs::read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use github_parts::github::app::AppId;
+use github_parts::github::token::TokenFactory;
+use github_parts::github::{GitHubHost, WebhookSecret};
+use parking_lot::{Mutex, RwLock};
+
+use crate::config::{ConfigError, OctoxConfig, ValidatedConfig};
+use crate::{SharedTokenFactory, Workflow, WorkflowConstructor};
+
+pub(crate) type SharedWebhookSecret = Arc<RwLock<WebhookSecret>>;
+pub(crate) type SharedWorkflow = Arc<RwLock<Arc<Box<dyn Workflow>>>>;
+
+/// Interval at which the credential files are checked for changes
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Reloads the private key and webhook secret while the server is running
+///
+/// The credentials are shared with the routes, and are swapped atomically when they change.
+/// Requests that are already being processed finish with the old credentials. The workflow is
+/// constructed again with the new private key, since it might have created its own clients.
+///
+/// If the new configuration is invalid, for example because a file has only been partially
+/// written, the old credentials are kept and the error is logged.
+#[derive(Debug)]
+pub(crate) struct Reloader {
+    config: OctoxConfig,
+    constructor: WorkflowConstructor,
+    github_host: GitHubHost,
+    app_id: AppId,
+    private_key: String,
+    webhook_secret: String,
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+    shared_token_factory: SharedTokenFactory,
+    shared_webhook_secret: SharedWebhookSecret,
+    shared_workflow: SharedWorkflow,
+}
+
+impl Reloader {
+    pub fn new(
+        config: OctoxConfig,
+        validated: ValidatedConfig,
+        constructor: WorkflowConstructor,
+    ) -> Self {
+        let token_factory = TokenFactory::new(
+            validated.github_host.clone(),
+            validated.app_id,
+            validated.private_key.clone(),
+        );
+        let workflow = constructor(
+            validated.github_host.clone(),
+            validated.app_id,
+            validated.private_key.clone(),
+        );
+
+        let files = [&config.private_key_path, &config.webhook_secret_path]
+            .into_iter()
+            .flatten()
+            .map(|path| (path.clone(), read(path).ok()))
+            .collect();
+
+        Self {
+            constructor,
+            github_host: validated.github_host,
+            app_id: validated.app_id,
+            private_key: validated.private_key.get().into(),
+            webhook_secret: validated.webhook_secret.get().into(),
+            files,
+            shared_token_factory: Arc::new(Mutex::new(token_factory)),
+            shared_webhook_secret: Arc::new(RwLock::new(validated.webhook_secret)),
+            shared_workflow: Arc::new(RwLock::new(Arc::new(workflow))),
+            config,
+        }
+    }
+
+    pub fn token_factory(&self) -> SharedTokenFactory {
+        self.shared_token_factory.clone()
+    }
+
+    pub fn webhook_secret(&self) -> SharedWebhookSecret {
+        self.shared_webhook_secret.clone()
+    }
+
+    pub fn workflow(&self) -> SharedWorkflow {
+        self.shared_workflow.clone()
+    }
+
+    /// Reloads the credentials when a file changes, or when the process receives `SIGHUP`
+    pub async fn watch(mut self) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut hangup = Hangup::new();
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if !self.files_changed() {
+                        continue;
+                    }
+                }
+                _ = hangup.recv() => {
+                    tracing::info!("received SIGHUP, reloading credentials");
+                }
+            }
+
+            match self.reload() {
+                Ok(true) => tracing::info!("reloaded credentials"),
+                Ok(false) => tracing::debug!("credentials have not changed"),
+                Err(error) => {
+                    tracing::error!(%error, "failed to reload credentials, keeping the old ones")
+                }
+            }
+        }
+    }
+
+    /// Swaps in the credentials from the configuration, and returns whether they changed
+    pub fn reload(&mut self) -> Result<bool, ConfigError> {
+        let validated = self.config.validate()?;
+
+        let private_key_changed = validated.private_key.get() != self.private_key;
+        let webhook_secret_changed = validated.webhook_secret.get() != self.webhook_secret;
+
+        if private_key_changed {
+            let token_factory = TokenFactory::new(
+                self.github_host.clone(),
+                self.app_id,
+                validated.private_key.clone(),
+            );
+            let workflow = (self.constructor)(
+                self.github_host.clone(),
+                self.app_id,
+                validated.private_key.clone(),
+            );
+
+            *self.shared_token_factory.lock() = token_factory;
+            *self.shared_workflow.write() = Arc::new(workflow);
+            self.private_key = validated.private_key.get().into();
+        }
+
+        if webhook_secret_changed {
+            self.webhook_secret = validated.webhook_secret.get().into();
+            *self.shared_webhook_secret.write() = validated.webhook_secret;
+        }
+
+        Ok(private_key_changed || webhook_secret_changed)
+    }
+
+    fn files_changed(&mut self) -> bool {
+        let mut changed = false;
+
+        for (path, content) in &mut self.files {
+            let current = read(path).ok();
+
+            if current != *content {
+                *content = current;
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+#[cfg(unix)]
+struct Hangup(Option<tokio::signal::unix::Signal>);
+
+#[cfg(unix)]
+impl Hangup {
+    fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let signal = signal(SignalKind::hangup())
+            .map_err(|error| tracing::warn!(%error, "failed to listen for SIGHUP"))
+            .ok();
+
+        Self(signal)
+    }
+
+    async fn recv(&mut self) {
+        match &mut self.0 {
+            Some(signal) => {
+                signal.recv().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Hangup;
+
+#[cfg(not(unix))]
+impl Hangup {
+    fn new() -> Self {
+        Self
+    }
+
+    async fn recv(&mut self) {
+        std::future::pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use async_trait::async_trait;
+    use github_parts::github::app::AppId;
+    use github_parts::github::{GitHubHost, PrivateKey};
+    use serde_json::json;
+
+    use crate::config::OctoxConfig;
+    use crate::{State, Step, Transition, Workflow, WorkflowError};
+
+    use super::Reloader;
+
+    const PRIVATE_KEY: &str = include_str!("../tests/fixtures/private-key.pem");
+
+    #[derive(Debug)]
+    struct Noop;
+
+    impl Workflow for Noop {
+        fn initial_step(&self) -> Box<dyn Step> {
+            Box::new(NoopStep)
+        }
+    }
+
+    struct NoopStep;
+
+    #[async_trait]
+    impl Step for NoopStep {
+        async fn next(self: Box<Self>, _state: &mut State) -> Result<Transition, WorkflowError> {
+            Ok(Transition::Complete(json!(null)))
+        }
+    }
+
+    fn constructor(_: GitHubHost, _: AppId, _: PrivateKey) -> Box<dyn Workflow> {
+        Box::new(Noop)
+    }
+
+    fn reloader(name: &str) -> (Reloader, PathBuf) {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, "old").unwrap();
+
+        let config = OctoxConfig {
+            app_id: Some("1".into()),
+            private_key: Some(PRIVATE_KEY.into()),
+            webhook_secret_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let validated = config.validate().unwrap();
+
+        (Reloader::new(config, validated, constructor), path)
+    }
+
+    #[test]
+    fn reload_swaps_changed_webhook_secret() {
+        let (mut reloader, path) = reloader("octox-reload-swaps");
+        let shared = reloader.webhook_secret();
+
+        std::fs::write(&path, "new").unwrap();
+
+        assert!(reloader.files_changed());
+        assert!(reloader.reload().unwrap());
+        assert_eq!("new", shared.read().get());
+    }
+
+    #[test]
+    fn reload_keeps_old_values_on_failure() {
+        let (mut reloader, path) = reloader("octox-reload-keeps");
+        let shared = reloader.webhook_secret();
+
+        std::fs::write(&path, "").unwrap();
+
+        assert!(reloader.reload().is_err());
+        assert_eq!("old", shared.read().get());
+    }
+
+    #[test]
+    fn reload_returns_false_without_changes() {
+        let (mut reloader, _) = reloader("octox-reload-unchanged");
+
+        assert!(!reloader.files_changed());
+        assert!(!reloader.reload().unwrap());
+    }
+}
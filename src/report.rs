@@ -0,0 +1,195 @@
+//! Reporting of errors to error tracking services
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::http::HeaderMap;
+
+use crate::{Delivery, Error, WorkflowError};
+
+#[cfg(feature = "sentry")]
+pub use self::sentry::SentryReporter;
+
+/// Backend that errors are reported to
+///
+/// Reporters are registered with [`Octox::error_reporter`](crate::Octox::error_reporter), and are
+/// called for errors that octox or a workflow caused while processing a webhook delivery. Errors
+/// that are caused by the request, e.g. an invalid signature or missing data, and outcomes that a
+/// workflow chose deliberately, e.g. [`WorkflowError::Skipped`], are not reported. Only errors that
+/// result in a server error status are.
+pub trait ErrorReporter: Debug + Send + Sync {
+    fn report(&self, error: &Error, context: &ErrorContext);
+}
+
+/// Webhook delivery that was being processed when an error occurred
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ErrorContext {
+    pub delivery_id: Option<String>,
+    pub event: Option<String>,
+    pub action: Option<String>,
+    pub repository: Option<String>,
+    pub installation: Option<u64>,
+}
+
+impl ErrorContext {
+    /// Reads the context from the headers and payload of a webhook delivery
+    ///
+    /// The payload might be the reason for the error, so it is read on a best-effort basis.
+    pub(crate) fn from_request(headers: &HeaderMap, body: &Bytes) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+
+        let event = header("X-GitHub-Event");
+        let delivery = Delivery::new(
+            header("X-GitHub-Delivery"),
+            event.as_deref().unwrap_or_default(),
+            serde_json::from_slice(body).unwrap_or_default(),
+        );
+
+        Self {
+            action: delivery.action().map(String::from),
+            repository: delivery.repository().map(String::from),
+            installation: delivery.installation(),
+            delivery_id: delivery.id,
+            event,
+        }
+    }
+}
+
+/// Error reporters that are shared with the routes
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ErrorReporters(Arc<Vec<Arc<dyn ErrorReporter>>>);
+
+impl ErrorReporters {
+    pub fn new(reporters: Vec<Arc<dyn ErrorReporter>>) -> Self {
+        Self(Arc::new(reporters))
+    }
+
+    /// Reports the error to all reporters, unless it was caused by the request
+    pub fn report(&self, error: &Error, context: impl FnOnce() -> ErrorContext) {
+        if !is_reportable(error) || self.0.is_empty() {
+            return;
+        }
+
+        let context = context();
+
+        for reporter in self.0.iter() {
+            reporter.report(error, &context);
+        }
+    }
+}
+
+/// Whether the error is a failure of the app
+///
+/// Client errors are caused by the request, and acknowledged deliveries are not failures. Refusing
+/// a delivery while shutting down or asking GitHub to retry it later are expected as well.
+fn is_reportable(error: &Error) -> bool {
+    error.status().is_server_error()
+        && !matches!(
+            error,
+            Error::ShuttingDown | Error::Workflow(WorkflowError::RetryLater(_))
+        )
+}
+
+#[cfg(feature = "sentry")]
+mod sentry {
+    use super::{ErrorContext, ErrorReporter};
+    use crate::Error;
+
+    /// Reports errors to Sentry, with the delivery's context as tags
+    ///
+    /// The reporter is registered automatically when octox is built with the `sentry` feature.
+    #[derive(Copy, Clone, Debug, Default)]
+    pub struct SentryReporter;
+
+    impl ErrorReporter for SentryReporter {
+        fn report(&self, error: &Error, context: &ErrorContext) {
+            sentry_core::with_scope(
+                |scope| {
+                    let tags = [
+                        ("github.delivery", context.delivery_id.clone()),
+                        ("github.event", context.event.clone()),
+                        ("github.action", context.action.clone()),
+                        ("github.repository", context.repository.clone()),
+                        (
+                            "github.installation",
+                            context.installation.map(|id| id.to_string()),
+                        ),
+                    ];
+
+                    for (key, value) in tags {
+                        if let Some(value) = value {
+                            scope.set_tag(key, value);
+                        }
+                    }
+                },
+                || sentry_core::capture_error(error),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Bytes;
+    use axum::http::HeaderMap;
+
+    use crate::{Error, WorkflowError};
+
+    use super::{is_reportable, ErrorContext};
+
+    #[test]
+    fn from_request_reads_headers_and_payload() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-GitHub-Event", "check_run".parse().unwrap());
+        headers.insert("X-GitHub-Delivery", "1".parse().unwrap());
+        let body = Bytes::from(
+            r#"{ "action": "created", "installation": { "id": 2 }, "repository": { "full_name": "devxbots/octox" } }"#,
+        );
+
+        let context = ErrorContext::from_request(&headers, &body);
+
+        assert_eq!(
+            ErrorContext {
+                delivery_id: Some("1".into()),
+                event: Some("check_run".into()),
+                action: Some("created".into()),
+                repository: Some("devxbots/octox".into()),
+                installation: Some(2),
+            },
+            context
+        );
+    }
+
+    #[test]
+    fn from_request_accepts_invalid_payload() {
+        let context = ErrorContext::from_request(&HeaderMap::new(), &Bytes::from("{"));
+
+        assert_eq!(ErrorContext::default(), context);
+    }
+
+    #[test]
+    fn is_reportable_ignores_errors_caused_by_request() {
+        assert!(!is_reportable(&Error::ShuttingDown));
+        assert!(!is_reportable(&Error::Payload(
+            serde_json::from_str::<serde_json::Value>("{").unwrap_err()
+        )));
+        assert!(!is_reportable(&Error::Workflow(
+            WorkflowError::Configuration
+        )));
+        assert!(!is_reportable(&Error::Workflow(WorkflowError::RetryLater(
+            "GitHub is unavailable".into()
+        ))));
+        assert!(!is_reportable(&Error::Workflow(
+            WorkflowError::MissingData("check run".into())
+        )));
+        assert!(is_reportable(&Error::Workflow(
+            WorkflowError::UnexpectedError(anyhow::anyhow!("check run is missing"))
+        )));
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::anyhow;
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use chrono::{TimeZone, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::{GitHubClient, API_VERSION};
+use crate::routes::health::{check_client, check_get};
+use crate::Error;
+
+/// Detailed report about the app and its connection to GitHub
+///
+/// The report reveals which app octox is authenticated as, and should only be served on an
+/// internal path. Each section is collected independently, and problems are listed in `errors`.
+#[derive(Debug, Default, Serialize)]
+pub struct Diagnostics {
+    github: GitHubDiagnostics,
+    app: Option<App>,
+    rate_limit: Option<RateLimits>,
+    jwt: Option<Jwt>,
+    errors: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct GitHubDiagnostics {
+    host: String,
+    api_version: String,
+    enterprise_version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct App {
+    id: u64,
+    slug: Option<String>,
+    name: Option<String>,
+    owner: Option<String>,
+}
+
+/// Rate limits of the installation that the report was collected with
+///
+/// Each installation has its own rate limits, and GitHub does not report them to the app itself.
+#[derive(Debug, Serialize)]
+struct RateLimits {
+    installation: u64,
+    core: Option<RateLimit>,
+    graphql: Option<RateLimit>,
+}
+
+#[derive(Debug, Serialize)]
+struct RateLimit {
+    limit: u64,
+    remaining: u64,
+    used: u64,
+    reset_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Jwt {
+    issued_at: String,
+    expires_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    iat: i64,
+    iss: String,
+    exp: i64,
+}
+
+#[tracing::instrument(skip(github_client))]
+pub async fn diagnostics(
+    Extension(github_client): Extension<GitHubClient>,
+) -> (StatusCode, Json<Diagnostics>) {
+    let mut diagnostics = Diagnostics {
+        github: GitHubDiagnostics {
+            host: github_client.github_host().get().into(),
+            api_version: API_VERSION.into(),
+            enterprise_version: None,
+        },
+        ..Default::default()
+    };
+
+    let github_client = check_client(&github_client);
+
+    let (app, rate_limit, meta) = futures::join!(
+        check_get::<Value>(&github_client, "app"),
+        installation_rate_limit(&github_client),
+        check_get::<Value>(&github_client, "meta"),
+    );
+
+    match app {
+        Ok(app) => diagnostics.app = parse_app(&app),
+        Err(error) => diagnostics
+            .errors
+            .push(format!("failed to get app: {}", error)),
+    }
+
+    match rate_limit {
+        Ok((installation, rate_limit)) => {
+            diagnostics.rate_limit = Some(parse_rate_limits(installation, &rate_limit))
+        }
+        Err(error) => diagnostics
+            .errors
+            .push(format!("failed to get rate limit: {}", error)),
+    }
+
+    // Only GitHub Enterprise Server reports its version
+    if let Ok(meta) = meta {
+        diagnostics.github.enterprise_version =
+            meta["installed_version"].as_str().map(String::from);
+    }
+
+    match github_client.app_token().map(|token| decode_jwt(&token)) {
+        Ok(Ok(jwt)) => diagnostics.jwt = Some(jwt),
+        Ok(Err(error)) => diagnostics.errors.push(error),
+        Err(error) => diagnostics
+            .errors
+            .push(format!("failed to create JWT: {}", error)),
+    }
+
+    let status_code = if diagnostics.errors.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    (status_code, Json(diagnostics))
+}
+
+/// Returns the rate limits of the app's first installation
+///
+/// The rate limit endpoint does not accept the app's JWT, so it is called with an installation
+/// token.
+async fn installation_rate_limit(github_client: &GitHubClient) -> Result<(u64, Value), Error> {
+    let installations: Vec<Value> =
+        check_get(github_client, "app/installations?per_page=1").await?;
+    let installation = installations
+        .first()
+        .and_then(|installation| installation["id"].as_u64())
+        .ok_or_else(|| anyhow!("the app has no installations"))?;
+
+    let rate_limit = check_get(&github_client.installation(installation), "rate_limit").await?;
+
+    Ok((installation, rate_limit))
+}
+
+fn parse_app(app: &Value) -> Option<App> {
+    Some(App {
+        id: app["id"].as_u64()?,
+        slug: app["slug"].as_str().map(String::from),
+        name: app["name"].as_str().map(String::from),
+        owner: app["owner"]["login"].as_str().map(String::from),
+    })
+}
+
+fn parse_rate_limits(installation: u64, rate_limit: &Value) -> RateLimits {
+    let parse = |resource: &Value| {
+        Some(RateLimit {
+            limit: resource["limit"].as_u64()?,
+            remaining: resource["remaining"].as_u64()?,
+            used: resource["used"].as_u64().unwrap_or_default(),
+            reset_at: timestamp(resource["reset"].as_i64()?),
+        })
+    };
+
+    RateLimits {
+        installation,
+        core: parse(&rate_limit["resources"]["core"]),
+        graphql: parse(&rate_limit["resources"]["graphql"]),
+    }
+}
+
+/// Reads the timestamps from the app's JWT
+///
+/// The JWT was signed by octox itself, so its signature does not need to be verified.
+fn decode_jwt(token: &str) -> Result<Jwt, String> {
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+
+    let claims = jsonwebtoken::decode::<Claims>(token, &DecodingKey::from_secret(&[]), &validation)
+        .map_err(|error| format!("failed to decode JWT: {}", error))?
+        .claims;
+
+    Ok(Jwt {
+        issued_at: timestamp(claims.iat),
+        expires_at: timestamp(claims.exp),
+    })
+}
+
+fn timestamp(seconds: i64) -> String {
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .map(|timestamp| timestamp.to_rfc3339())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    use super::{decode_jwt, parse_rate_limits, Claims};
+
+    #[test]
+    fn decode_jwt_returns_expiry() {
+        let claims = Claims {
+            iat: 1_650_000_000,
+            iss: "1".into(),
+            exp: 1_650_000_600,
+        };
+        let token = encode(
+            &Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &EncodingKey::from_rsa_pem(include_bytes!("../../tests/fixtures/private-key.pem"))
+                .unwrap(),
+        )
+        .unwrap();
+
+        let jwt = decode_jwt(&token).unwrap();
+
+        assert_eq!("2022-04-15T05:30:00+00:00", jwt.expires_at);
+    }
+
+    #[test]
+    fn parse_rate_limits_reads_core_and_graphql() {
+        let rate_limit = json!({
+            "resources": {
+                "core": { "limit": 5000, "remaining": 4999, "used": 1, "reset": 1650000000 },
+                "graphql": { "limit": 5000, "remaining": 5000, "used": 0, "reset": 1650000000 }
+            }
+        });
+
+        let rate_limits = parse_rate_limits(1, &rate_limit);
+
+        assert_eq!(4999, rate_limits.core.unwrap().remaining);
+        assert_eq!(5000, rate_limits.graphql.unwrap().remaining);
+    }
+}
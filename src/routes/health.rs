@@ -1,75 +1,248 @@
+use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use anyhow::anyhow;
 use axum::http::StatusCode;
 use axum::{Extension, Json};
-use github_parts::github::token::TokenFactory;
+use chrono::Utc;
+use futures::future::{BoxFuture, FutureExt, Shared};
 use parking_lot::Mutex;
-use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
 
-use crate::{Error, GitHubHost, SharedTokenFactory};
+use crate::client::{GitHubClient, GitHubError};
+use crate::shutdown::Deliveries;
+use crate::Error;
 
 #[derive(Debug, Serialize)]
 pub struct Health {
     github: String,
 }
 
-#[derive(Debug, Serialize)]
-struct Claims {
-    iat: i64,
-    iss: String,
-    exp: i64,
+/// Reports whether octox can authenticate with GitHub
+///
+/// The route predates the liveness and readiness probes, and shares the readiness probe's cached
+/// GitHub check so that it does not call GitHub on every request.
+#[tracing::instrument(skip(probe))]
+pub async fn health(Extension(probe): Extension<GitHubProbe>) -> (StatusCode, Json<Health>) {
+    let component = probe.check().await;
+
+    match component.status {
+        ComponentStatus::Ok => (
+            StatusCode::OK,
+            Json(Health {
+                github: "ok".into(),
+            }),
+        ),
+        ComponentStatus::Error => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(Health {
+                github: component.message.unwrap_or_default(),
+            }),
+        ),
+    }
 }
 
-#[tracing::instrument]
-pub async fn health(
-    Extension(token_factory): Extension<SharedTokenFactory>,
-    Extension(github_host): Extension<GitHubHost>,
-) -> (StatusCode, Json<Health>) {
-    let mut status_code = StatusCode::OK;
-
-    let github = match check_github(&token_factory, &github_host).await {
-        Ok(_) => "ok".into(),
-        Err(error) => {
-            status_code = StatusCode::INTERNAL_SERVER_ERROR;
-            error.to_string()
-        }
-    };
+/// Longest time that a check waits for GitHub
+///
+/// Probes must respond before the orchestrator's timeout, so checks neither retry nor wait for a
+/// rate limit to reset.
+const GITHUB_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
-    (status_code, Json(Health { github }))
+/// Returns a client for checks, which fails fast instead of retrying or waiting for a rate limit
+pub(super) fn check_client(github_client: &GitHubClient) -> GitHubClient {
+    github_client
+        .clone()
+        .max_retries(0)
+        .max_wait(Duration::ZERO)
+}
+
+/// Sends a `GET` request with a client from [`check_client`], and gives up after a few seconds
+pub(super) async fn check_get<T: DeserializeOwned>(
+    github_client: &GitHubClient,
+    path: &str,
+) -> Result<T, Error> {
+    let result = tokio::time::timeout(GITHUB_CHECK_TIMEOUT, github_client.get::<T>(path))
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "GitHub did not respond within {} seconds",
+                GITHUB_CHECK_TIMEOUT.as_secs()
+            )
+        })?;
+
+    match result {
+        Ok(value) => Ok(value),
+        Err(GitHubError::Token(message)) => Err(Error::Configuration(message)),
+        Err(error) => Err(error.into()),
+    }
 }
 
 #[tracing::instrument]
-async fn check_github(
-    token_factory: &Arc<Mutex<TokenFactory>>,
-    github_host: &GitHubHost,
-) -> Result<(), Error> {
-    let endpoint = format!("{}/app", github_host.get());
-
-    let token = match token_factory.lock().app() {
-        Ok(token) => token,
-        Err(error) => {
-            return match error {
-                github_parts::error::Error::Configuration(_, message) => {
-                    Err(Error::Configuration(message))
+async fn check_github(github_client: &GitHubClient) -> Result<(), Error> {
+    check_get::<Value>(&check_client(github_client), "app").await?;
+    Ok(())
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Liveness {
+    status: ComponentStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    ready: bool,
+    components: Components,
+}
+
+#[derive(Debug, Serialize)]
+struct Components {
+    github: GitHubComponent,
+    queue: QueueComponent,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct GitHubComponent {
+    status: ComponentStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    checked_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct QueueComponent {
+    status: ComponentStatus,
+    depth: usize,
+    draining: bool,
+}
+
+/// Check of the app's authentication with GitHub whose result is cached
+///
+/// Readiness probes run every few seconds, and calling GitHub each time would use up the rate
+/// limit. Concurrent probes wait for the same check instead of starting their own.
+#[derive(Clone, Debug)]
+pub struct GitHubProbe {
+    github_client: GitHubClient,
+    ttl: Duration,
+    state: Arc<Mutex<ProbeState>>,
+}
+
+#[derive(Default)]
+struct ProbeState {
+    cached: Option<(Instant, GitHubComponent)>,
+    running: Option<Shared<BoxFuture<'static, GitHubComponent>>>,
+}
+
+impl Debug for ProbeState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProbeState")
+            .field("cached", &self.cached)
+            .field("running", &self.running.is_some())
+            .finish()
+    }
+}
+
+impl GitHubProbe {
+    pub fn new(github_client: GitHubClient, ttl: Duration) -> Self {
+        Self {
+            github_client,
+            ttl,
+            state: Arc::new(Mutex::new(ProbeState::default())),
+        }
+    }
+
+    async fn check(&self) -> GitHubComponent {
+        // The lock is only held to look up the cache, and never while waiting for GitHub
+        let running = {
+            let mut state = self.state.lock();
+
+            if let Some((checked_at, component)) = &state.cached {
+                if checked_at.elapsed() < self.ttl {
+                    return component.clone();
                 }
-                _ => Err(Error::UnexpectedError(error.into())),
             }
-        }
-    };
 
-    let response = Client::new()
-        .get(endpoint)
-        .header("Authorization", format!("Bearer {}", token.get()))
-        .header("Accept", "application/vnd.github.v3+json")
-        .header("User-Agent", "devxbots/octox")
-        .send()
-        .await?;
+            match &state.running {
+                Some(running) => running.clone(),
+                None => {
+                    let running = self.clone().run().boxed().shared();
+                    state.running = Some(running.clone());
+                    running
+                }
+            }
+        };
 
-    if response.status().is_success() {
-        Ok(())
-    } else {
-        let text = response.text().await?;
-        Err(Error::UnexpectedError(anyhow::Error::msg(text)))
+        running.await
+    }
+
+    async fn run(self) -> GitHubComponent {
+        let component = match check_github(&self.github_client).await {
+            Ok(_) => GitHubComponent {
+                status: ComponentStatus::Ok,
+                message: None,
+                checked_at: Utc::now().to_rfc3339(),
+            },
+            Err(error) => GitHubComponent {
+                status: ComponentStatus::Error,
+                message: Some(error.to_string()),
+                checked_at: Utc::now().to_rfc3339(),
+            },
+        };
+
+        let mut state = self.state.lock();
+        state.cached = Some((Instant::now(), component.clone()));
+        state.running = None;
+
+        component
     }
 }
+
+/// Reports that the process is running, without calling any external services
+#[tracing::instrument]
+pub async fn liveness() -> Json<Liveness> {
+    Json(Liveness {
+        status: ComponentStatus::Ok,
+    })
+}
+
+/// Reports whether octox can process deliveries
+///
+/// octox is not ready when it cannot authenticate with GitHub, or when it is shutting down.
+#[tracing::instrument(skip(probe, deliveries))]
+pub async fn readiness(
+    Extension(probe): Extension<GitHubProbe>,
+    Extension(deliveries): Extension<Deliveries>,
+) -> (StatusCode, Json<Readiness>) {
+    let github = probe.check().await;
+    let draining = deliveries.is_draining();
+
+    let queue = QueueComponent {
+        status: if draining {
+            ComponentStatus::Error
+        } else {
+            ComponentStatus::Ok
+        },
+        depth: deliveries.depth(),
+        draining,
+    };
+
+    let ready = github.status == ComponentStatus::Ok && !draining;
+    let status_code = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let components = Components { github, queue };
+
+    (status_code, Json(Readiness { ready, components }))
+}
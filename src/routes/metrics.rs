@@ -0,0 +1,14 @@
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::Extension;
+
+use crate::metrics::Metrics;
+use crate::shutdown::Deliveries;
+
+#[tracing::instrument(skip_all)]
+pub async fn metrics(Extension(deliveries): Extension<Deliveries>) -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        Metrics::global().render(deliveries.depth()),
+    )
+}
@@ -1,5 +1,13 @@
-pub use self::health::health;
+pub use self::diagnostics::diagnostics;
+pub use self::health::{health, liveness, readiness, GitHubProbe};
+#[cfg(feature = "metrics")]
+pub use self::metrics::metrics;
+#[cfg(feature = "testing")]
+pub(crate) use self::webhook::deserialize_event;
 pub use self::webhook::webhook;
 
+mod diagnostics;
 mod health;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod webhook;
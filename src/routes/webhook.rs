@@ -1,32 +1,170 @@
 use std::sync::Arc;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
 
 use axum::body::Bytes;
 use axum::http::HeaderMap;
+use axum::response::Response;
 use axum::{Extension, Json};
 use github_parts::event::Event;
 use github_parts::github::WebhookSecret;
 use serde_json::Value;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
 
-use crate::auth::{verify_signature, AuthError};
+use crate::auth::{verify_signature, AuthError, SignatureVerification};
+use crate::config::Environment;
 use crate::error::Error;
-use crate::workflow::Workflow;
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
+use crate::problem::error_response;
+use crate::recording::{record_delivery, Recorder};
+use crate::reload::{SharedWebhookSecret, SharedWorkflow};
+use crate::report::{ErrorContext, ErrorReporters};
+use crate::shutdown::Deliveries;
+use crate::{Delivery, Workflow};
 
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(skip(body))]
 pub async fn webhook(
     headers: HeaderMap,
     body: Bytes,
-    Extension(webhook_secret): Extension<WebhookSecret>,
-    Extension(workflow): Extension<Arc<Box<dyn Workflow>>>,
+    Extension(webhook_secret): Extension<SharedWebhookSecret>,
+    Extension(workflow): Extension<SharedWorkflow>,
+    Extension(deliveries): Extension<Deliveries>,
+    Extension(error_reporters): Extension<ErrorReporters>,
+    Extension(environment): Extension<Environment>,
+    Extension(verification): Extension<SignatureVerification>,
+    Extension(recorder): Extension<Option<Arc<Recorder>>>,
+) -> Result<Json<Value>, Response> {
+    let webhook_secret = webhook_secret.read().clone();
+    let workflow = workflow.read().clone();
+
+    let result = handle_delivery(
+        &headers,
+        &body,
+        &webhook_secret,
+        verification,
+        recorder,
+        workflow,
+        &deliveries,
+    )
+    .await;
+
+    if let Err(error) = &result {
+        error_reporters.report(error, || ErrorContext::from_request(&headers, &body));
+    }
+
+    result.map_err(|error| {
+        error_response(
+            &error,
+            environment,
+            get_header(&headers, "X-GitHub-Delivery").ok(),
+        )
+    })
+}
+
+async fn handle_delivery(
+    headers: &HeaderMap,
+    body: &Bytes,
+    webhook_secret: &WebhookSecret,
+    verification: SignatureVerification,
+    recorder: Option<Arc<Recorder>>,
+    workflow: Arc<Box<dyn Workflow>>,
+    deliveries: &Deliveries,
 ) -> Result<Json<Value>, Error> {
-    let signature = get_signature(&headers)?;
-    verify_signature(&body, &signature, &webhook_secret)?;
+    if verification == SignatureVerification::Required {
+        if let Err(error) = verify_request(headers, body, webhook_secret) {
+            #[cfg(feature = "metrics")]
+            Metrics::global().record_signature_failure(&error);
+
+            return Err(error.into());
+        }
+    }
+
+    let event_type = get_event(headers)?;
+    let delivery_id = get_header(headers, "X-GitHub-Delivery").ok();
+
+    // Deliveries that are refused while the server is draining are neither recorded nor parsed
+    let _guard = match deliveries.start(delivery_id.clone(), &event_type) {
+        Some(guard) => guard,
+        None => {
+            #[cfg(feature = "metrics")]
+            Metrics::global().record_unavailable(&event_type);
+
+            return Err(Error::ShuttingDown);
+        }
+    };
+
+    if let Some(recorder) = recorder {
+        record_delivery(recorder, headers.clone(), body.clone()).await;
+    }
 
-    let event_type = get_event(&headers)?;
-    let event = deserialize_event(&event_type, &body)?;
+    let event = deserialize_event(&event_type, body)?;
+    let delivery = Delivery::new(delivery_id, &event_type, serde_json::from_slice(body)?);
+    let span = delivery_span(&delivery);
 
-    let body = workflow.execute(event).await?;
+    #[cfg(feature = "metrics")]
+    let action = delivery.action().map(String::from);
+
+    #[cfg(feature = "metrics")]
+    let started_at = Instant::now();
+
+    let result = workflow
+        .execute_delivery(event, delivery)
+        .instrument(span)
+        .await;
+
+    #[cfg(feature = "metrics")]
+    Metrics::global().record_workflow(
+        &event_type,
+        action.as_deref(),
+        &result,
+        started_at.elapsed(),
+    );
+
+    Ok(Json(result?))
+}
+
+/// Creates the root span of the delivery's trace, which all of the workflow's spans belong to
+fn delivery_span(delivery: &Delivery) -> Span {
+    let span = tracing::info_span!(
+        parent: None,
+        "delivery",
+        delivery.id = Empty,
+        github.event = %delivery.event,
+        github.action = Empty,
+        github.installation = Empty,
+        github.repository = Empty,
+    );
+
+    if let Some(id) = &delivery.id {
+        span.record("delivery.id", id.as_str());
+
+        #[cfg(feature = "otel")]
+        crate::telemetry::set_delivery_trace(&span, id);
+    }
+    if let Some(action) = delivery.action() {
+        span.record("github.action", action);
+    }
+    if let Some(installation) = delivery.installation() {
+        span.record("github.installation", installation);
+    }
+    if let Some(repository) = delivery.repository() {
+        span.record("github.repository", repository);
+    }
+
+    span
+}
 
-    Ok(Json(body))
+#[tracing::instrument(skip(body, webhook_secret))]
+fn verify_request(
+    headers: &HeaderMap,
+    body: &Bytes,
+    webhook_secret: &WebhookSecret,
+) -> Result<(), AuthError> {
+    let signature = get_signature(headers)?;
+    verify_signature(body, &signature, webhook_secret)
 }
 
 #[tracing::instrument]
@@ -49,7 +187,7 @@ fn get_header(headers: &HeaderMap, header: &str) -> Result<String, AuthError> {
 }
 
 #[tracing::instrument(skip(body))]
-fn deserialize_event(event_type: &str, body: &Bytes) -> Result<Event, Error> {
+pub(crate) fn deserialize_event(event_type: &str, body: &Bytes) -> Result<Event, Error> {
     let event = match event_type {
         "check_run" => Event::CheckRun(serde_json::from_slice(body)?),
         _ => Event::Unsupported(serde_json::from_slice(body)?),
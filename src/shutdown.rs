@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use parking_lot::Mutex;
+use tokio::sync::watch;
+
+use crate::Error;
+
+/// Time that running workflows get to finish after a shutdown has been requested
+pub(crate) const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Future that resolves when the server should shut down
+pub(crate) struct ShutdownSignal(Pin<Box<dyn Future<Output = ()> + Send>>);
+
+impl ShutdownSignal {
+    pub fn new(signal: impl Future<Output = ()> + Send + 'static) -> Self {
+        Self(Box::pin(signal))
+    }
+
+    /// Waits for `SIGTERM` or `Ctrl+C`
+    pub fn terminate() -> Self {
+        Self::new(async {
+            let ctrl_c = async {
+                if let Err(error) = tokio::signal::ctrl_c().await {
+                    tracing::warn!(%error, "failed to listen for Ctrl+C");
+                    std::future::pending::<()>().await;
+                }
+            };
+
+            #[cfg(unix)]
+            let terminate = async {
+                use tokio::signal::unix::{signal, SignalKind};
+
+                match signal(SignalKind::terminate()) {
+                    Ok(mut signal) => {
+                        signal.recv().await;
+                    }
+                    Err(error) => {
+                        tracing::warn!(%error, "failed to listen for SIGTERM");
+                        std::future::pending::<()>().await;
+                    }
+                }
+            };
+
+            #[cfg(not(unix))]
+            let terminate = std::future::pending::<()>();
+
+            tokio::select! {
+                _ = ctrl_c => {},
+                _ = terminate => {},
+            }
+        })
+    }
+
+    pub async fn wait(self) {
+        self.0.await
+    }
+}
+
+impl Debug for ShutdownSignal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ShutdownSignal")
+    }
+}
+
+/// Delivery that was still being processed when the shutdown deadline passed
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AbandonedDelivery {
+    pub id: Option<String>,
+    pub event: String,
+    pub running_for: Duration,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    id: Option<String>,
+    event: String,
+    started_at: Instant,
+}
+
+/// Tracks the deliveries that are being processed, and whether the server is draining
+///
+/// Once the server starts draining, new deliveries are refused so that the running workflows can
+/// finish before the process exits.
+#[derive(Clone, Debug)]
+pub(crate) struct Deliveries {
+    next_id: Arc<AtomicU64>,
+    in_flight: Arc<Mutex<HashMap<u64, InFlight>>>,
+    draining: Arc<watch::Sender<bool>>,
+}
+
+impl Deliveries {
+    pub fn new() -> Self {
+        let (draining, _) = watch::channel(false);
+
+        Self {
+            next_id: Arc::new(AtomicU64::new(0)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            draining: Arc::new(draining),
+        }
+    }
+
+    /// Registers a delivery, or returns `None` if the server is draining
+    ///
+    /// The delivery is removed again when the returned guard is dropped.
+    pub fn start(&self, id: Option<String>, event: &str) -> Option<DeliveryGuard> {
+        if self.is_draining() {
+            return None;
+        }
+
+        let key = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.lock().insert(
+            key,
+            InFlight {
+                id,
+                event: event.into(),
+                started_at: Instant::now(),
+            },
+        );
+
+        Some(DeliveryGuard {
+            key,
+            deliveries: self.clone(),
+        })
+    }
+
+    pub fn drain(&self) {
+        tracing::info!("shutting down, waiting for running workflows to finish");
+        self.draining.send_replace(true);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    /// Waits until the server starts draining
+    pub async fn draining(&self) {
+        let mut receiver = self.draining.subscribe();
+
+        while !*receiver.borrow_and_update() {
+            if receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Returns the number of deliveries that are being processed
+    pub fn depth(&self) -> usize {
+        self.in_flight.lock().len()
+    }
+
+    pub fn abandoned(&self) -> Vec<AbandonedDelivery> {
+        self.in_flight
+            .lock()
+            .values()
+            .map(|in_flight| AbandonedDelivery {
+                id: in_flight.id.clone(),
+                event: in_flight.event.clone(),
+                running_for: in_flight.started_at.elapsed(),
+            })
+            .collect()
+    }
+}
+
+/// Runs the server until it has shut down
+///
+/// The server must stop accepting connections once the deliveries start draining. If it takes
+/// longer than the timeout to finish the running deliveries, they are abandoned, logged, and
+/// returned.
+pub(crate) async fn run_until_shutdown<F, E>(
+    server: F,
+    deliveries: &Deliveries,
+    timeout: Duration,
+) -> Result<Vec<AbandonedDelivery>, Error>
+where
+    F: Future<Output = Result<(), E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => {
+            result.context("failed to start HTTP server")?;
+        }
+        _ = deliveries.draining() => {
+            if let Ok(result) = tokio::time::timeout(timeout, &mut server).await {
+                result.context("failed to shut down HTTP server")?;
+            } else {
+                let abandoned = deliveries.abandoned();
+
+                for delivery in &abandoned {
+                    tracing::warn!(
+                        id = ?delivery.id,
+                        event = %delivery.event,
+                        running_for = ?delivery.running_for,
+                        "abandoned delivery during shutdown"
+                    );
+                }
+
+                return Ok(abandoned);
+            }
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+#[derive(Debug)]
+pub(crate) struct DeliveryGuard {
+    key: u64,
+    deliveries: Deliveries,
+}
+
+impl Drop for DeliveryGuard {
+    fn drop(&mut self) {
+        self.deliveries.in_flight.lock().remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deliveries;
+
+    #[test]
+    fn start_refuses_deliveries_while_draining() {
+        let deliveries = Deliveries::new();
+
+        deliveries.drain();
+
+        assert!(deliveries.start(None, "ping").is_none());
+    }
+
+    #[test]
+    fn guard_removes_delivery_when_dropped() {
+        let deliveries = Deliveries::new();
+
+        let guard = deliveries.start(Some("1".into()), "check_run").unwrap();
+        assert_eq!(1, deliveries.abandoned().len());
+
+        drop(guard);
+        assert!(deliveries.abandoned().is_empty());
+    }
+
+    #[tokio::test]
+    async fn draining_resolves_after_drain() {
+        let deliveries = Deliveries::new();
+
+        let waiting = deliveries.clone();
+        let handle = tokio::spawn(async move { waiting.draining().await });
+
+        deliveries.drain();
+
+        handle.await.unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+
+/// Background tasks that are aborted when they are dropped
+///
+/// The tasks of a router, e.g. the reloader, are tied to the router by adding them as an
+/// extension, so that they stop once the last clone of the router has been dropped.
+#[derive(Debug, Default)]
+pub(crate) struct BackgroundTasks {
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl BackgroundTasks {
+    pub fn spawn(&mut self, task: impl Future<Output = ()> + Send + 'static) {
+        self.handles.push(tokio::spawn(task));
+    }
+}
+
+impl Drop for BackgroundTasks {
+    fn drop(&mut self) {
+        for handle in &self.handles {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::oneshot;
+
+    use super::BackgroundTasks;
+
+    #[tokio::test]
+    async fn drop_aborts_tasks() {
+        let (sender, receiver) = oneshot::channel::<()>();
+
+        let mut tasks = BackgroundTasks::default();
+        tasks.spawn(async move {
+            let _sender = sender;
+            std::future::pending::<()>().await
+        });
+
+        drop(tasks);
+
+        assert!(receiver.await.is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! OpenTelemetry tracing
+//!
+//! octox does not install a `tracing` subscriber itself. Applications add the layer returned by
+//! [`layer`] to their subscriber to export spans to an OpenTelemetry collector over OTLP:
+//!
+//! ```ignore
+//! tracing_subscriber::registry()
+//!     .with(tracing_subscriber::fmt::layer())
+//!     .with(octox::telemetry::layer("my-github-app")?)
+//!     .init();
+//!
+//! let result = octox.serve().await;
+//! octox::telemetry::shutdown();
+//! ```
+//!
+//! The exporter is configured with the standard `OTEL_EXPORTER_OTLP_ENDPOINT` and
+//! `OTEL_EXPORTER_OTLP_TIMEOUT` environment variables, and defaults to a collector on
+//! `localhost:4317`.
+//!
+//! Each webhook delivery is traced in its own trace, whose id is derived from the delivery's
+//! `X-GitHub-Delivery` header. Requests that [`GitHubClient`](crate::client::GitHubClient) sends
+//! carry the trace context in the `traceparent` header.
+
+use anyhow::Context as _;
+use opentelemetry::propagation::Injector;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::trace::Tracer;
+use opentelemetry::sdk::Resource;
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use tracing::{Span, Subscriber};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::Error;
+
+/// Returns a layer that exports spans to an OpenTelemetry collector
+///
+/// The exporter runs on the Tokio runtime, so the layer must be created from within it.
+pub fn layer<S>(service_name: &str) -> Result<OpenTelemetryLayer<S, Tracer>, Error>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_env())
+        .with_trace_config(
+            opentelemetry::sdk::trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                service_name.to_string(),
+            )])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .context("failed to install OpenTelemetry exporter")?;
+
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Exports the spans that have not been sent to the collector yet
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Places the span in the trace of the webhook delivery
+///
+/// GitHub uses the same delivery id when a delivery is redelivered, which puts all attempts to
+/// process a delivery into the same trace.
+pub(crate) fn set_delivery_trace(span: &Span, delivery_id: &str) {
+    let (trace_id, span_id) = match delivery_trace_ids(delivery_id) {
+        Some(ids) => ids,
+        None => return,
+    };
+
+    let parent = SpanContext::new(
+        TraceId::from_u128(trace_id),
+        SpanId::from_u64(span_id),
+        TraceFlags::SAMPLED,
+        true,
+        TraceState::default(),
+    );
+
+    span.set_parent(Context::new().with_remote_span_context(parent));
+}
+
+/// Adds the context of the current span to the headers of an outgoing request
+pub(crate) fn inject_context(headers: &mut HeaderMap) {
+    let context = Span::current().context();
+
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Derives the trace id and the id of the trace's remote parent span from a delivery id
+///
+/// Delivery ids are UUIDs, which have the same size as trace ids.
+fn delivery_trace_ids(delivery_id: &str) -> Option<(u128, u64)> {
+    let hex: String = delivery_id.chars().filter(|c| *c != '-').collect();
+
+    if hex.len() != 32 {
+        return None;
+    }
+
+    let trace_id = u128::from_str_radix(&hex, 16).ok().filter(|id| *id != 0)?;
+    let span_id = (trace_id as u64).max(1);
+
+    Some((trace_id, span_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::delivery_trace_ids;
+
+    #[test]
+    fn delivery_trace_ids_uses_uuid_as_trace_id() {
+        let (trace_id, span_id) =
+            delivery_trace_ids("72d3162e-cc78-11e3-81ab-4c9367dc0958").unwrap();
+
+        assert_eq!(0x72d3162ecc7811e381ab4c9367dc0958, trace_id);
+        assert_eq!(0x81ab4c9367dc0958, span_id);
+    }
+
+    #[test]
+    fn delivery_trace_ids_ignores_other_ids() {
+        assert!(delivery_trace_ids("not-a-uuid").is_none());
+        assert!(delivery_trace_ids("00000000-0000-0000-0000-000000000000").is_none());
+    }
+}
@@ -0,0 +1,874 @@
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use super::Fixture;
+
+const DEFAULT_REPOSITORY: &str = "octocat/hello-world";
+const DEFAULT_SENDER: &str = "octocat";
+const DEFAULT_INSTALLATION: u64 = 1;
+const DEFAULT_HEAD_SHA: &str = "ec26c3e57ca3a959ca5aad62de7213c562f8c821";
+const DEFAULT_BEFORE_SHA: &str = "6113728f27ae82c7b1a177c8d03f9e96e0adf246";
+
+/// Builder for the payload of a webhook event
+///
+/// The builders generate complete payloads with the same structure as the ones that GitHub sends,
+/// filled with defaults that can be overridden. Fields without a dedicated method can be set with
+/// `field`, which takes a JSON pointer, e.g. `/pull_request/draft`.
+pub trait EventBuilder {
+    /// Type of the event, which is sent in the `X-GitHub-Event` header
+    fn event_type(&self) -> &'static str;
+
+    fn build(&self) -> Value;
+
+    /// Returns the payload as a fixture, e.g. to sign it or to run a workflow with it
+    fn fixture(&self) -> Fixture {
+        Fixture::new(self.event_type(), self.build().to_string())
+    }
+}
+
+/// Fields that all events share
+#[derive(Clone, Debug)]
+struct Common {
+    action: &'static str,
+    repository: String,
+    installation: Option<u64>,
+    sender: String,
+    overrides: Vec<(String, Value)>,
+}
+
+impl Common {
+    fn new(action: &'static str) -> Self {
+        Self {
+            action,
+            repository: DEFAULT_REPOSITORY.into(),
+            installation: Some(DEFAULT_INSTALLATION),
+            sender: DEFAULT_SENDER.into(),
+            overrides: Vec::new(),
+        }
+    }
+
+    fn payload(&self, fields: Value) -> Value {
+        let mut payload = json!({
+            "action": self.action,
+            "repository": repository(&self.repository),
+            "sender": user(&self.sender),
+        });
+
+        if let Some(installation) = self.installation {
+            payload["installation"] = json!({
+                "id": installation,
+                "node_id": format!("MDIzOkludGVncmF0aW9uSW5zdGFsbGF0aW9u{}", installation),
+            });
+        }
+
+        if let (Some(payload), Value::Object(fields)) = (payload.as_object_mut(), fields) {
+            payload.extend(fields);
+        }
+
+        for (pointer, value) in &self.overrides {
+            set_pointer(&mut payload, pointer, value.clone());
+        }
+
+        payload
+    }
+}
+
+macro_rules! common_fields {
+    ($builder:ty) => {
+        impl $builder {
+            /// Sets the full name of the repository, e.g. `octocat/hello-world`
+            pub fn repo(mut self, full_name: &str) -> Self {
+                self.common.repository = full_name.into();
+                self
+            }
+
+            /// Sets the id of the installation, which defaults to 1
+            pub fn installation(mut self, installation: u64) -> Self {
+                self.common.installation = Some(installation);
+                self
+            }
+
+            /// Removes the installation, which GitHub omits for events that are not sent to apps
+            pub fn without_installation(mut self) -> Self {
+                self.common.installation = None;
+                self
+            }
+
+            /// Sets the login of the user that triggered the event
+            pub fn sender(mut self, login: &str) -> Self {
+                self.common.sender = login.into();
+                self
+            }
+
+            /// Sets a field of the payload, which is given as a JSON pointer
+            ///
+            /// Missing objects along the pointer are created. Panics if the pointer refers to an
+            /// array item that does not exist.
+            pub fn field(mut self, pointer: &str, value: Value) -> Self {
+                self.common.overrides.push((pointer.into(), value));
+                self
+            }
+        }
+    };
+}
+
+/// Builder for `check_run` events
+#[derive(Clone, Debug)]
+pub struct CheckRunEventBuilder {
+    common: Common,
+    id: u64,
+    name: String,
+    head_sha: String,
+    status: &'static str,
+    conclusion: Option<String>,
+    requested_action: Option<String>,
+}
+
+impl CheckRunEventBuilder {
+    fn new(action: &'static str, status: &'static str) -> Self {
+        Self {
+            common: Common::new(action),
+            id: 1,
+            name: "octox".into(),
+            head_sha: DEFAULT_HEAD_SHA.into(),
+            status,
+            conclusion: None,
+            requested_action: None,
+        }
+    }
+
+    pub fn created() -> Self {
+        Self::new("created", "queued")
+    }
+
+    pub fn rerequested() -> Self {
+        Self::new("rerequested", "completed").conclusion("failure")
+    }
+
+    pub fn completed() -> Self {
+        Self::new("completed", "completed").conclusion("success")
+    }
+
+    /// Event for a button of the check run that a user clicked
+    pub fn requested_action(identifier: &str) -> Self {
+        let mut builder = Self::new("requested_action", "completed").conclusion("neutral");
+        builder.requested_action = Some(identifier.into());
+        builder
+    }
+
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn head_sha(mut self, head_sha: &str) -> Self {
+        self.head_sha = head_sha.into();
+        self
+    }
+
+    pub fn conclusion(mut self, conclusion: &str) -> Self {
+        self.conclusion = Some(conclusion.into());
+        self
+    }
+}
+
+common_fields!(CheckRunEventBuilder);
+
+impl EventBuilder for CheckRunEventBuilder {
+    fn event_type(&self) -> &'static str {
+        "check_run"
+    }
+
+    fn build(&self) -> Value {
+        let repository = &self.common.repository;
+        let now = timestamp();
+        let completed_at = (self.status == "completed").then(|| now.clone());
+
+        let mut fields = json!({
+            "check_run": {
+                "id": self.id,
+                "node_id": format!("CR_{}", self.id),
+                "head_sha": self.head_sha,
+                "external_id": "",
+                "url": format!("https://api.github.com/repos/{}/check-runs/{}", repository, self.id),
+                "html_url": format!("https://github.com/{}/runs/{}", repository, self.id),
+                "details_url": format!("https://github.com/{}/runs/{}", repository, self.id),
+                "status": self.status,
+                "conclusion": self.conclusion,
+                "started_at": now,
+                "completed_at": completed_at,
+                "output": {
+                    "title": null,
+                    "summary": null,
+                    "text": null,
+                    "annotations_count": 0,
+                    "annotations_url": format!(
+                        "https://api.github.com/repos/{}/check-runs/{}/annotations",
+                        repository, self.id
+                    ),
+                },
+                "name": self.name,
+                "check_suite": check_suite(repository, self.id, &self.head_sha, self.status, self.conclusion.as_deref()),
+                "app": app(),
+                "pull_requests": [],
+            },
+        });
+
+        if let Some(identifier) = &self.requested_action {
+            fields["requested_action"] = json!({ "identifier": identifier });
+        }
+
+        self.common.payload(fields)
+    }
+}
+
+/// Builder for `check_suite` events
+#[derive(Clone, Debug)]
+pub struct CheckSuiteEventBuilder {
+    common: Common,
+    id: u64,
+    head_sha: String,
+    status: &'static str,
+    conclusion: Option<String>,
+}
+
+impl CheckSuiteEventBuilder {
+    fn new(action: &'static str, status: &'static str, conclusion: Option<&str>) -> Self {
+        Self {
+            common: Common::new(action),
+            id: 1,
+            head_sha: DEFAULT_HEAD_SHA.into(),
+            status,
+            conclusion: conclusion.map(String::from),
+        }
+    }
+
+    pub fn requested() -> Self {
+        Self::new("requested", "queued", None)
+    }
+
+    pub fn rerequested() -> Self {
+        Self::new("rerequested", "completed", Some("failure"))
+    }
+
+    pub fn completed() -> Self {
+        Self::new("completed", "completed", Some("success"))
+    }
+
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub fn head_sha(mut self, head_sha: &str) -> Self {
+        self.head_sha = head_sha.into();
+        self
+    }
+
+    pub fn conclusion(mut self, conclusion: &str) -> Self {
+        self.conclusion = Some(conclusion.into());
+        self
+    }
+}
+
+common_fields!(CheckSuiteEventBuilder);
+
+impl EventBuilder for CheckSuiteEventBuilder {
+    fn event_type(&self) -> &'static str {
+        "check_suite"
+    }
+
+    fn build(&self) -> Value {
+        self.common.payload(json!({
+            "check_suite": check_suite(
+                &self.common.repository,
+                self.id,
+                &self.head_sha,
+                self.status,
+                self.conclusion.as_deref(),
+            ),
+        }))
+    }
+}
+
+/// Builder for `pull_request` events
+#[derive(Clone, Debug)]
+pub struct PullRequestEventBuilder {
+    common: Common,
+    number: u64,
+    title: String,
+    head_ref: String,
+    head_sha: String,
+    base_ref: String,
+    state: &'static str,
+    merged: bool,
+    draft: bool,
+}
+
+impl PullRequestEventBuilder {
+    fn new(action: &'static str) -> Self {
+        Self {
+            common: Common::new(action),
+            number: 1,
+            title: "Update the README".into(),
+            head_ref: "changes".into(),
+            head_sha: DEFAULT_HEAD_SHA.into(),
+            base_ref: "main".into(),
+            state: "open",
+            merged: false,
+            draft: false,
+        }
+    }
+
+    pub fn opened() -> Self {
+        Self::new("opened")
+    }
+
+    pub fn reopened() -> Self {
+        Self::new("reopened")
+    }
+
+    /// Event for new commits that were pushed to the pull request's branch
+    pub fn synchronize() -> Self {
+        Self::new("synchronize")
+    }
+
+    pub fn closed() -> Self {
+        let mut builder = Self::new("closed");
+        builder.state = "closed";
+        builder
+    }
+
+    pub fn number(mut self, number: u64) -> Self {
+        self.number = number;
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn head(mut self, head_ref: &str, head_sha: &str) -> Self {
+        self.head_ref = head_ref.into();
+        self.head_sha = head_sha.into();
+        self
+    }
+
+    pub fn base(mut self, base_ref: &str) -> Self {
+        self.base_ref = base_ref.into();
+        self
+    }
+
+    pub fn merged(mut self, merged: bool) -> Self {
+        self.merged = merged;
+        self
+    }
+
+    pub fn draft(mut self, draft: bool) -> Self {
+        self.draft = draft;
+        self
+    }
+}
+
+common_fields!(PullRequestEventBuilder);
+
+impl EventBuilder for PullRequestEventBuilder {
+    fn event_type(&self) -> &'static str {
+        "pull_request"
+    }
+
+    fn build(&self) -> Value {
+        let repository = &self.common.repository;
+        let now = timestamp();
+        let closed_at = (self.state == "closed").then(|| now.clone());
+        let merged_at = self.merged.then(|| now.clone());
+
+        self.common.payload(json!({
+            "number": self.number,
+            "pull_request": {
+                "url": format!("https://api.github.com/repos/{}/pulls/{}", repository, self.number),
+                "id": self.number,
+                "node_id": format!("PR_{}", self.number),
+                "html_url": format!("https://github.com/{}/pull/{}", repository, self.number),
+                "diff_url": format!("https://github.com/{}/pull/{}.diff", repository, self.number),
+                "patch_url": format!("https://github.com/{}/pull/{}.patch", repository, self.number),
+                "issue_url": format!("https://api.github.com/repos/{}/issues/{}", repository, self.number),
+                "number": self.number,
+                "state": self.state,
+                "locked": false,
+                "title": self.title,
+                "user": user(&self.common.sender),
+                "body": null,
+                "created_at": now,
+                "updated_at": now,
+                "closed_at": closed_at,
+                "merged_at": merged_at,
+                "merge_commit_sha": null,
+                "draft": self.draft,
+                "head": branch(repository, &self.head_ref, &self.head_sha),
+                "base": branch(repository, &self.base_ref, DEFAULT_BEFORE_SHA),
+                "merged": self.merged,
+                "mergeable": null,
+                "comments": 0,
+                "commits": 1,
+                "additions": 1,
+                "deletions": 1,
+                "changed_files": 1,
+            },
+        }))
+    }
+}
+
+/// Builder for `issue_comment` events, which are also sent for comments on pull requests
+#[derive(Clone, Debug)]
+pub struct IssueCommentEventBuilder {
+    common: Common,
+    id: u64,
+    issue: u64,
+    body: String,
+    pull_request: bool,
+}
+
+impl IssueCommentEventBuilder {
+    fn new(action: &'static str) -> Self {
+        Self {
+            common: Common::new(action),
+            id: 1,
+            issue: 1,
+            body: "Looks good to me".into(),
+            pull_request: false,
+        }
+    }
+
+    pub fn created() -> Self {
+        Self::new("created")
+    }
+
+    pub fn edited() -> Self {
+        Self::new("edited")
+    }
+
+    pub fn deleted() -> Self {
+        Self::new("deleted")
+    }
+
+    pub fn id(mut self, id: u64) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Sets the number of the issue or pull request
+    pub fn issue(mut self, number: u64) -> Self {
+        self.issue = number;
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Marks the issue as a pull request
+    pub fn on_pull_request(mut self) -> Self {
+        self.pull_request = true;
+        self
+    }
+}
+
+common_fields!(IssueCommentEventBuilder);
+
+impl EventBuilder for IssueCommentEventBuilder {
+    fn event_type(&self) -> &'static str {
+        "issue_comment"
+    }
+
+    fn build(&self) -> Value {
+        let repository = &self.common.repository;
+        let now = timestamp();
+
+        let mut issue = json!({
+            "url": format!("https://api.github.com/repos/{}/issues/{}", repository, self.issue),
+            "id": self.issue,
+            "node_id": format!("I_{}", self.issue),
+            "html_url": format!("https://github.com/{}/issues/{}", repository, self.issue),
+            "number": self.issue,
+            "title": "Spelling error in the README file",
+            "user": user(&self.common.sender),
+            "labels": [],
+            "state": "open",
+            "locked": false,
+            "comments": 1,
+            "created_at": now,
+            "updated_at": now,
+            "closed_at": null,
+            "body": null,
+        });
+
+        if self.pull_request {
+            issue["pull_request"] = json!({
+                "url": format!("https://api.github.com/repos/{}/pulls/{}", repository, self.issue),
+                "html_url": format!("https://github.com/{}/pull/{}", repository, self.issue),
+            });
+        }
+
+        self.common.payload(json!({
+            "issue": issue,
+            "comment": {
+                "url": format!("https://api.github.com/repos/{}/issues/comments/{}", repository, self.id),
+                "html_url": format!(
+                    "https://github.com/{}/issues/{}#issuecomment-{}",
+                    repository, self.issue, self.id
+                ),
+                "id": self.id,
+                "node_id": format!("IC_{}", self.id),
+                "user": user(&self.common.sender),
+                "created_at": now,
+                "updated_at": now,
+                "author_association": "OWNER",
+                "body": self.body,
+            },
+        }))
+    }
+}
+
+/// Builder for `push` events
+///
+/// Push events have no action, so the payload has no `action` field.
+#[derive(Clone, Debug)]
+pub struct PushEventBuilder {
+    common: Common,
+    git_ref: String,
+    before: String,
+    after: String,
+}
+
+impl PushEventBuilder {
+    pub fn new() -> Self {
+        Self {
+            common: Common::new(""),
+            git_ref: "refs/heads/main".into(),
+            before: DEFAULT_BEFORE_SHA.into(),
+            after: DEFAULT_HEAD_SHA.into(),
+        }
+    }
+
+    /// Sets the pushed ref, e.g. `refs/heads/main`
+    pub fn git_ref(mut self, git_ref: &str) -> Self {
+        self.git_ref = git_ref.into();
+        self
+    }
+
+    pub fn before(mut self, sha: &str) -> Self {
+        self.before = sha.into();
+        self
+    }
+
+    pub fn after(mut self, sha: &str) -> Self {
+        self.after = sha.into();
+        self
+    }
+}
+
+impl Default for PushEventBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+common_fields!(PushEventBuilder);
+
+impl EventBuilder for PushEventBuilder {
+    fn event_type(&self) -> &'static str {
+        "push"
+    }
+
+    fn build(&self) -> Value {
+        let repository = &self.common.repository;
+
+        let mut payload = self.common.payload(json!({
+            "ref": self.git_ref,
+            "before": self.before,
+            "after": self.after,
+            "created": false,
+            "deleted": false,
+            "forced": false,
+            "base_ref": null,
+            "compare": format!(
+                "https://github.com/{}/compare/{}...{}",
+                repository,
+                &self.before[..self.before.len().min(12)],
+                &self.after[..self.after.len().min(12)]
+            ),
+            "commits": [],
+            "head_commit": null,
+            "pusher": { "name": self.common.sender, "email": null },
+        }));
+
+        if let Some(payload) = payload.as_object_mut() {
+            payload.remove("action");
+        }
+
+        payload
+    }
+}
+
+fn timestamp() -> String {
+    Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn user(login: &str) -> Value {
+    let id = login.bytes().fold(0u64, |id, byte| {
+        id.wrapping_mul(31).wrapping_add(byte as u64)
+    }) % 100_000_000;
+    let api = format!("https://api.github.com/users/{}", login);
+
+    json!({
+        "login": login,
+        "id": id,
+        "node_id": format!("U_{}", id),
+        "avatar_url": format!("https://avatars.githubusercontent.com/u/{}?v=4", id),
+        "gravatar_id": "",
+        "url": api,
+        "html_url": format!("https://github.com/{}", login),
+        "followers_url": format!("{}/followers", api),
+        "following_url": format!("{}/following{{/other_user}}", api),
+        "gists_url": format!("{}/gists{{/gist_id}}", api),
+        "starred_url": format!("{}/starred{{/owner}}{{/repo}}", api),
+        "subscriptions_url": format!("{}/subscriptions", api),
+        "organizations_url": format!("{}/orgs", api),
+        "repos_url": format!("{}/repos", api),
+        "events_url": format!("{}/events{{/privacy}}", api),
+        "received_events_url": format!("{}/received_events", api),
+        "type": "User",
+        "site_admin": false,
+    })
+}
+
+fn repository(full_name: &str) -> Value {
+    const API_URLS: [(&str, &str); 37] = [
+        ("forks_url", "/forks"),
+        ("keys_url", "/keys{/key_id}"),
+        ("collaborators_url", "/collaborators{/collaborator}"),
+        ("teams_url", "/teams"),
+        ("hooks_url", "/hooks"),
+        ("issue_events_url", "/issues/events{/number}"),
+        ("events_url", "/events"),
+        ("assignees_url", "/assignees{/user}"),
+        ("branches_url", "/branches{/branch}"),
+        ("tags_url", "/tags"),
+        ("blobs_url", "/git/blobs{/sha}"),
+        ("git_tags_url", "/git/tags{/sha}"),
+        ("git_refs_url", "/git/refs{/sha}"),
+        ("trees_url", "/git/trees{/sha}"),
+        ("statuses_url", "/statuses/{sha}"),
+        ("languages_url", "/languages"),
+        ("stargazers_url", "/stargazers"),
+        ("contributors_url", "/contributors"),
+        ("subscribers_url", "/subscribers"),
+        ("subscription_url", "/subscription"),
+        ("commits_url", "/commits{/sha}"),
+        ("git_commits_url", "/git/commits{/sha}"),
+        ("comments_url", "/comments{/number}"),
+        ("issue_comment_url", "/issues/comments{/number}"),
+        ("contents_url", "/contents/{+path}"),
+        ("compare_url", "/compare/{base}...{head}"),
+        ("merges_url", "/merges"),
+        ("archive_url", "/{archive_format}{/ref}"),
+        ("downloads_url", "/downloads"),
+        ("issues_url", "/issues{/number}"),
+        ("pulls_url", "/pulls{/number}"),
+        ("milestones_url", "/milestones{/number}"),
+        (
+            "notifications_url",
+            "/notifications{?since,all,participating}",
+        ),
+        ("labels_url", "/labels{/name}"),
+        ("releases_url", "/releases{/id}"),
+        ("deployments_url", "/deployments"),
+        ("url", ""),
+    ];
+
+    let (owner, name) = full_name.split_once('/').unwrap_or((full_name, full_name));
+    let html = format!("https://github.com/{}", full_name);
+
+    let mut repository = json!({
+        "id": 1,
+        "node_id": "R_1",
+        "name": name,
+        "full_name": full_name,
+        "private": false,
+        "owner": user(owner),
+        "html_url": html,
+        "description": null,
+        "fork": false,
+        "created_at": "2022-01-01T00:00:00Z",
+        "updated_at": "2022-01-01T00:00:00Z",
+        "pushed_at": "2022-01-01T00:00:00Z",
+        "git_url": format!("git://github.com/{}.git", full_name),
+        "ssh_url": format!("git@github.com:{}.git", full_name),
+        "clone_url": format!("{}.git", html),
+        "svn_url": html,
+        "homepage": null,
+        "size": 0,
+        "stargazers_count": 0,
+        "watchers_count": 0,
+        "language": "Rust",
+        "has_issues": true,
+        "has_projects": true,
+        "has_downloads": true,
+        "has_wiki": true,
+        "has_pages": false,
+        "forks_count": 0,
+        "mirror_url": null,
+        "archived": false,
+        "disabled": false,
+        "open_issues_count": 0,
+        "license": null,
+        "visibility": "public",
+        "forks": 0,
+        "open_issues": 0,
+        "watchers": 0,
+        "default_branch": "main",
+    });
+
+    for (field, path) in API_URLS {
+        repository[field] = json!(format!(
+            "https://api.github.com/repos/{}{}",
+            full_name, path
+        ));
+    }
+
+    repository
+}
+
+fn app() -> Value {
+    json!({
+        "id": 1,
+        "node_id": "A_1",
+        "owner": user("devxbots"),
+        "name": "octox",
+        "description": "",
+        "external_url": "https://github.com/devxbots/octox",
+        "html_url": "https://github.com/apps/octox",
+        "created_at": "2022-01-01T00:00:00Z",
+        "updated_at": "2022-01-01T00:00:00Z",
+        "permissions": {
+            "checks": "write",
+            "contents": "read",
+            "metadata": "read",
+            "pull_requests": "write",
+        },
+        "events": [],
+    })
+}
+
+fn check_suite(
+    repository: &str,
+    id: u64,
+    head_sha: &str,
+    status: &str,
+    conclusion: Option<&str>,
+) -> Value {
+    json!({
+        "id": id,
+        "node_id": format!("CS_{}", id),
+        "head_branch": "main",
+        "head_sha": head_sha,
+        "status": status,
+        "conclusion": conclusion,
+        "url": format!("https://api.github.com/repos/{}/check-suites/{}", repository, id),
+        "before": DEFAULT_BEFORE_SHA,
+        "after": head_sha,
+        "pull_requests": [],
+        "app": app(),
+        "created_at": "2022-01-01T00:00:00Z",
+        "updated_at": "2022-01-01T00:00:00Z",
+    })
+}
+
+fn branch(repository: &str, git_ref: &str, sha: &str) -> Value {
+    let owner = repository.split('/').next().unwrap_or(repository);
+
+    json!({
+        "label": format!("{}:{}", owner, git_ref),
+        "ref": git_ref,
+        "sha": sha,
+        "user": user(owner),
+        "repo": self::repository(repository),
+    })
+}
+
+/// Sets the value at the JSON pointer, and creates missing objects along the way
+///
+/// Array items cannot be created, so a pointer to a missing item panics.
+fn set_pointer(payload: &mut Value, pointer: &str, value: Value) {
+    let mut current = payload;
+
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+
+        if !current.is_object() && !current.is_array() {
+            *current = json!({});
+        }
+
+        current = match current {
+            Value::Array(items) => match token.parse::<usize>() {
+                Ok(index) if index < items.len() => &mut items[index],
+                _ => panic!(
+                    "pointer {} refers to array item {} that does not exist",
+                    pointer, token
+                ),
+            },
+            Value::Object(fields) => fields.entry(token).or_insert(Value::Null),
+            _ => unreachable!("value was replaced with an object"),
+        };
+    }
+
+    *current = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::set_pointer;
+
+    #[test]
+    fn set_pointer_creates_missing_objects() {
+        let mut payload = json!({ "pull_request": { "title": "Fix" } });
+
+        set_pointer(&mut payload, "/pull_request/labels", json!([]));
+        set_pointer(&mut payload, "/organization/login", json!("devxbots"));
+
+        assert_eq!(
+            json!({
+                "pull_request": { "title": "Fix", "labels": [] },
+                "organization": { "login": "devxbots" }
+            }),
+            payload
+        );
+    }
+
+    #[test]
+    fn set_pointer_replaces_array_items() {
+        let mut payload = json!({ "commits": [{ "id": "a" }] });
+
+        set_pointer(&mut payload, "/commits/0/id", json!("b"));
+
+        assert_eq!(json!({ "commits": [{ "id": "b" }] }), payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "pointer /commits/1/id refers to array item 1 that does not exist")]
+    fn set_pointer_panics_on_missing_array_items() {
+        let mut payload = json!({ "commits": [{ "id": "a" }] });
+
+        set_pointer(&mut payload, "/commits/1/id", json!("c"));
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::body::Bytes;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use axum::{Extension, Json, Router, Server};
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use crate::Error;
+
+/// Stand-in for the GitHub API that runs in the test's process
+///
+/// The server answers the requests that workflows commonly send with ready-made handlers:
+///
+/// - `POST /app/installations/{id}/access_tokens` returns an installation token
+/// - `GET /app` returns the app, which can be replaced with [`MockGitHub::app`]
+/// - `POST /repos/{owner}/{repo}/check-runs` creates a check run, and `PATCH` and `GET` on
+///   `/repos/{owner}/{repo}/check-runs/{id}` update and return it
+/// - `POST /repos/{owner}/{repo}/issues/{number}/comments` creates a comment
+/// - `GET /repos/{owner}/{repo}/contents/{path}` returns files added with [`MockGitHub::file`]
+/// - Lists added with [`MockGitHub::paginate`] are split into pages that are linked with `Link`
+///   headers
+///
+/// Other requests can be answered with [`MockGitHub::respond`], and anything else is answered
+/// with `404 Not Found`. All requests are recorded, so tests can assert on what a workflow sent.
+/// The server is passed to octox with [`Octox::github_host`](crate::Octox::github_host) and
+/// [`MockGitHub::url`], and is stopped when it is dropped.
+#[derive(Debug)]
+pub struct MockGitHub {
+    url: String,
+    inner: Arc<Inner>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+/// Request that was received by [`MockGitHub`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+    /// JSON body of the request, or `null` if it had none
+    pub body: Value,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    requests: Mutex<Vec<RecordedRequest>>,
+    responses: Mutex<HashMap<(Method, String), (StatusCode, Value)>>,
+    lists: Mutex<HashMap<String, (Vec<Value>, usize)>>,
+    files: Mutex<HashMap<(String, String), String>>,
+    app: Mutex<Option<Value>>,
+    check_runs: Mutex<BTreeMap<u64, Value>>,
+    comments: Mutex<Vec<Value>>,
+    next_id: Mutex<u64>,
+    url: String,
+}
+
+impl MockGitHub {
+    /// Starts the server on a random local port
+    ///
+    /// The server runs on the Tokio runtime, so it must be started from within it.
+    pub fn start() -> Result<Self, Error> {
+        let listener = TcpListener::bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+            .context("failed to bind mock GitHub server")?;
+        let url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .context("failed to get address of mock GitHub server")?
+        );
+
+        let inner = Arc::new(Inner {
+            url: url.clone(),
+            ..Default::default()
+        });
+
+        let router = Router::new()
+            .fallback(any(handle))
+            .layer(Extension(inner.clone()));
+        let (shutdown, signal) = oneshot::channel::<()>();
+
+        let server = Server::from_tcp(listener)
+            .context("failed to create mock GitHub server")?
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(async move {
+                signal.await.ok();
+            });
+
+        tokio::spawn(async move {
+            if let Err(error) = server.await {
+                tracing::error!(%error, "mock GitHub server failed");
+            }
+        });
+
+        Ok(Self {
+            url,
+            inner,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// URL of the server, which is used as the GitHub host
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Sets the app that `GET /app` returns
+    pub fn app(&self, app: Value) -> &Self {
+        *self.inner.app.lock() = Some(app);
+        self
+    }
+
+    /// Adds a file that `GET /repos/{repository}/contents/{path}` returns
+    pub fn file(&self, repository: &str, path: &str, content: &str) -> &Self {
+        self.inner.files.lock().insert(
+            (repository.into(), path.trim_start_matches('/').into()),
+            content.into(),
+        );
+        self
+    }
+
+    /// Serves the items at the path in pages of `per_page` items
+    pub fn paginate(&self, path: &str, items: Vec<Value>, per_page: usize) -> &Self {
+        self.inner
+            .lists
+            .lock()
+            .insert(normalize(path), (items, per_page.max(1)));
+        self
+    }
+
+    /// Answers requests with the method and path with the status and body
+    ///
+    /// Responses that are added this way take precedence over the ready-made handlers.
+    pub fn respond(&self, method: Method, path: &str, status: StatusCode, body: Value) -> &Self {
+        self.inner
+            .responses
+            .lock()
+            .insert((method, normalize(path)), (status, body));
+        self
+    }
+
+    /// Returns all requests in the order in which they were received
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.inner.requests.lock().clone()
+    }
+
+    /// Returns the requests with the method and path, e.g. `POST` and `/repos/o/r/check-runs`
+    pub fn requests_to(&self, method: Method, path: &str) -> Vec<RecordedRequest> {
+        let path = normalize(path);
+
+        self.inner
+            .requests
+            .lock()
+            .iter()
+            .filter(|request| request.method == method && request.path == path)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the check runs in the order in which they were created, with all updates applied
+    pub fn check_runs(&self) -> Vec<Value> {
+        self.inner.check_runs.lock().values().cloned().collect()
+    }
+
+    /// Returns the comments in the order in which they were created
+    pub fn comments(&self) -> Vec<Value> {
+        self.inner.comments.lock().clone()
+    }
+}
+
+impl Drop for MockGitHub {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+impl Inner {
+    fn next_id(&self) -> u64 {
+        let mut next_id = self.next_id.lock();
+        *next_id += 1;
+        *next_id
+    }
+}
+
+async fn handle(
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+    Extension(inner): Extension<Arc<Inner>>,
+) -> Response {
+    let path = normalize(uri.path());
+    let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    inner.requests.lock().push(RecordedRequest {
+        method: method.clone(),
+        path: path.clone(),
+        query: uri.query().map(String::from),
+        headers,
+        body: body.clone(),
+    });
+
+    if let Some((status, body)) = inner.responses.lock().get(&(method.clone(), path.clone())) {
+        return (*status, Json(body.clone())).into_response();
+    }
+
+    if method == Method::GET {
+        if let Some(response) = page(&inner, &path, uri.query()) {
+            return response;
+        }
+    }
+
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (Method::POST, ["app", "installations", installation, "access_tokens"]) => (
+            StatusCode::CREATED,
+            Json(json!({
+                "token": format!("installation-token-{}", installation),
+                "expires_at": (Utc::now() + chrono::Duration::hours(1)).to_rfc3339(),
+            })),
+        )
+            .into_response(),
+        (Method::GET, ["app"]) => {
+            let app = inner.app.lock().clone().unwrap_or_else(|| {
+                json!({ "id": 1, "slug": "octox", "name": "octox", "owner": { "login": "devxbots" } })
+            });
+
+            Json(app).into_response()
+        }
+        (Method::POST, ["repos", owner, repo, "check-runs"]) => {
+            let id = inner.next_id();
+            let mut check_run = json!({
+                "id": id,
+                "html_url": format!("https://github.com/{}/{}/runs/{}", owner, repo, id),
+            });
+            merge(&mut check_run, body);
+
+            inner.check_runs.lock().insert(id, check_run.clone());
+
+            (StatusCode::CREATED, Json(check_run)).into_response()
+        }
+        (method @ (Method::GET | Method::PATCH), ["repos", _, _, "check-runs", id]) => {
+            let id = id.parse::<u64>().unwrap_or_default();
+            let mut check_runs = inner.check_runs.lock();
+
+            match check_runs.get_mut(&id) {
+                Some(check_run) => {
+                    if method == Method::PATCH {
+                        merge(check_run, body);
+                    }
+
+                    Json(check_run.clone()).into_response()
+                }
+                None => not_found(),
+            }
+        }
+        (Method::POST, ["repos", owner, repo, "issues", number, "comments"]) => {
+            let id = inner.next_id();
+            let mut comment = json!({
+                "id": id,
+                "html_url": format!(
+                    "https://github.com/{}/{}/issues/{}#issuecomment-{}",
+                    owner, repo, number, id
+                ),
+            });
+            merge(&mut comment, body);
+
+            inner.comments.lock().push(comment.clone());
+
+            (StatusCode::CREATED, Json(comment)).into_response()
+        }
+        (Method::GET, ["repos", owner, repo, "contents", file @ ..]) => {
+            let repository = format!("{}/{}", owner, repo);
+            let file = file.join("/");
+
+            match inner.files.lock().get(&(repository, file.clone())) {
+                Some(content) => Json(json!({
+                    "type": "file",
+                    "encoding": "base64",
+                    "name": file.rsplit('/').next().unwrap_or_default(),
+                    "path": file,
+                    "size": content.len(),
+                    "content": base64(content.as_bytes()),
+                }))
+                .into_response(),
+                None => not_found(),
+            }
+        }
+        _ => not_found(),
+    }
+}
+
+/// Returns the requested page of a paginated list, with a link to the next page
+fn page(inner: &Inner, path: &str, query: Option<&str>) -> Option<Response> {
+    let lists = inner.lists.lock();
+    let (items, per_page) = lists.get(path)?;
+
+    let pairs: Vec<(&str, &str)> = query
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect();
+    let page = pairs
+        .iter()
+        .find(|(name, _)| *name == "page")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+
+    let start = (page - 1) * per_page;
+    let total = items.len();
+    let items: Vec<Value> = items.iter().skip(start).take(*per_page).cloned().collect();
+    let mut headers = HeaderMap::new();
+
+    if start + per_page < total {
+        let mut query: Vec<String> = pairs
+            .iter()
+            .filter(|(name, _)| *name != "page")
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        query.push(format!("page={}", page + 1));
+
+        let link = format!("<{}{}?{}>; rel=\"next\"", inner.url, path, query.join("&"));
+        headers.insert("Link", link.parse().ok()?);
+    }
+
+    Some((headers, Json(Value::Array(items))).into_response())
+}
+
+fn not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "message": "Not Found" })),
+    )
+        .into_response()
+}
+
+fn normalize(path: &str) -> String {
+    let path = path.split('?').next().unwrap_or_default();
+    format!("/{}", path.trim_start_matches('/'))
+}
+
+/// Copies the fields of a request body into a resource
+fn merge(resource: &mut Value, body: Value) {
+    if let (Some(resource), Value::Object(fields)) = (resource.as_object_mut(), body) {
+        resource.extend(fields);
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> shift & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64, normalize};
+
+    #[test]
+    fn base64_pads_output() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("b2N0b3g6IHRlc3Q=", base64(b"octox: test"));
+    }
+
+    #[test]
+    fn normalize_adds_leading_slash_and_removes_query() {
+        assert_eq!("/app", normalize("app"));
+        assert_eq!(
+            "/repos/o/r/pulls",
+            normalize("/repos/o/r/pulls?per_page=100")
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Helpers to test workflows without a server
+//!
+//! A [`Fixture`] is a webhook payload together with its event type. It can be turned into the
+//! [`Event`] and [`Delivery`] that the webhook route would insert into the workflow's state, or
+//! into a signed webhook request that is sent to the app's [`Router`] in-process:
+//!
+//! ```ignore
+//! let fixture = Fixture::load("check_run", "tests/fixtures/check_run.created.json")?;
+//!
+//! let run = WorkflowHarness::new(&MyWorkflow)
+//!     .fixture(&fixture)?
+//!     .state(Config::default())
+//!     .run()
+//!     .await;
+//!
+//! run.assert_steps(&["CreateCheckRun", "CompleteCheckRun"]);
+//! run.assert_completed_with(json!("check run completed"));
+//!
+//! let response = router.oneshot(fixture.signed_request("/", &webhook_secret)?).await?;
+//! ```
+//!
+//! Payloads can also be generated with the builders for each event type, e.g.
+//! `PullRequestEventBuilder::opened().repo("devxbots/octox").number(5).fixture()`.
+//!
+//! Requests that workflows send to GitHub can be answered by a [`MockGitHub`] server, which records
+//! them for assertions.
+//!
+//! The helpers require the `testing` feature.
+
+use std::path::Path;
+
+use anyhow::Context;
+use axum::body::{Body, Bytes};
+use axum::http::Request;
+use github_parts::event::Event;
+use github_parts::github::WebhookSecret;
+use serde_json::Value;
+
+use crate::routes::deserialize_event;
+use crate::workflow::run_steps;
+use crate::{Delivery, Error, State, Workflow, WorkflowError};
+
+pub use self::events::{
+    CheckRunEventBuilder, CheckSuiteEventBuilder, EventBuilder, IssueCommentEventBuilder,
+    PullRequestEventBuilder, PushEventBuilder,
+};
+pub use self::github::{MockGitHub, RecordedRequest};
+
+mod events;
+mod github;
+
+/// Computes the `X-Hub-Signature-256` header that GitHub sends with the payload
+pub fn sign(payload: &[u8], webhook_secret: &WebhookSecret) -> Result<String, Error> {
+    Ok(crate::auth::sign(payload, webhook_secret)?)
+}
+
+/// Webhook payload of a given event type
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    event_type: String,
+    delivery_id: Option<String>,
+    payload: Bytes,
+}
+
+impl Fixture {
+    pub fn new(event_type: &str, payload: impl Into<Bytes>) -> Self {
+        Self {
+            event_type: event_type.into(),
+            delivery_id: None,
+            payload: payload.into(),
+        }
+    }
+
+    /// Reads the payload from a JSON file
+    pub fn load(event_type: &str, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let payload = std::fs::read(path)
+            .with_context(|| format!("failed to read fixture {}", path.display()))?;
+
+        Ok(Self::new(event_type, payload))
+    }
+
+    /// Sets the id of the delivery, which is sent as the `X-GitHub-Delivery` header
+    pub fn delivery_id(mut self, delivery_id: &str) -> Self {
+        self.delivery_id = Some(delivery_id.into());
+        self
+    }
+
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Deserializes the payload into the event that the webhook route passes to workflows
+    pub fn event(&self) -> Result<Event, Error> {
+        deserialize_event(&self.event_type, &self.payload)
+    }
+
+    pub fn delivery(&self) -> Result<Delivery, Error> {
+        Ok(Delivery::new(
+            self.delivery_id.clone(),
+            &self.event_type,
+            serde_json::from_slice(&self.payload)?,
+        ))
+    }
+
+    /// Computes the `X-Hub-Signature-256` header of the payload
+    pub fn signature(&self, webhook_secret: &WebhookSecret) -> Result<String, Error> {
+        sign(&self.payload, webhook_secret)
+    }
+
+    /// Builds a webhook request for the path that is signed with the secret
+    ///
+    /// The request can be sent to the app's router with `tower::ServiceExt::oneshot`.
+    pub fn signed_request(
+        &self,
+        path: &str,
+        webhook_secret: &WebhookSecret,
+    ) -> Result<Request<Body>, Error> {
+        let mut request = Request::post(path)
+            .header("Content-Type", "application/json")
+            .header("X-GitHub-Event", &self.event_type)
+            .header("X-Hub-Signature-256", self.signature(webhook_secret)?);
+
+        if let Some(delivery_id) = &self.delivery_id {
+            request = request.header("X-GitHub-Delivery", delivery_id);
+        }
+
+        let request = request
+            .body(Body::from(self.payload.clone()))
+            .context("failed to build webhook request")?;
+
+        Ok(request)
+    }
+}
+
+/// Runs a workflow directly, with a state that is prepared by the test
+#[derive(Debug)]
+pub struct WorkflowHarness<'a> {
+    workflow: &'a dyn Workflow,
+    state: State,
+}
+
+impl<'a> WorkflowHarness<'a> {
+    /// Creates a harness whose state is the workflow's initial state
+    pub fn new(workflow: &'a dyn Workflow) -> Self {
+        Self {
+            state: workflow.initial_state(),
+            workflow,
+        }
+    }
+
+    /// Inserts the fixture's event and delivery into the state, like the webhook route does
+    pub fn fixture(mut self, fixture: &Fixture) -> Result<Self, Error> {
+        self.state.insert(fixture.event()?);
+        self.state.insert(fixture.delivery()?);
+        Ok(self)
+    }
+
+    /// Inserts a value into the state
+    pub fn state<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.state.insert(value);
+        self
+    }
+
+    /// Runs the workflow from its initial step
+    ///
+    /// A workflow that overrides [`Workflow::run`] is executed with the default implementation.
+    pub async fn run(mut self) -> WorkflowRun {
+        let mut steps = Vec::new();
+        let result = run_steps(self.workflow.initial_step(), &mut self.state, |name| {
+            steps.push(name)
+        })
+        .await;
+
+        WorkflowRun {
+            result,
+            steps,
+            state: self.state,
+        }
+    }
+}
+
+/// Outcome of a workflow that was run by a [`WorkflowHarness`]
+#[derive(Debug)]
+pub struct WorkflowRun {
+    pub result: Result<Value, WorkflowError>,
+    /// Names of the steps in the order in which they were executed
+    pub steps: Vec<&'static str>,
+    /// State after the last step
+    pub state: State,
+}
+
+impl WorkflowRun {
+    /// Asserts that the workflow completed with the value
+    #[track_caller]
+    pub fn assert_completed_with(&self, expected: Value) {
+        match &self.result {
+            Ok(value) => assert_eq!(&expected, value, "workflow completed with another value"),
+            Err(error) => panic!("expected workflow to complete, but it failed: {}", error),
+        }
+    }
+
+    /// Asserts that the workflow failed with an error with the code, e.g. `skipped`
+    #[track_caller]
+    pub fn assert_failed_with(&self, code: &str) {
+        match &self.result {
+            Ok(value) => panic!("expected workflow to fail, but it completed with {}", value),
+            Err(error) => assert_eq!(code, error.code(), "workflow failed with {}", error),
+        }
+    }
+
+    /// Asserts that the steps were executed in the order
+    ///
+    /// Steps are named after their type by default, e.g. `my_app::steps::CreateCheckRun`, which
+    /// matches both the full name and `CreateCheckRun`.
+    #[track_caller]
+    pub fn assert_steps(&self, expected: &[&str]) {
+        let matches = self.steps.len() == expected.len()
+            && self
+                .steps
+                .iter()
+                .zip(expected)
+                .all(|(name, expected)| step_matches(name, expected));
+
+        assert!(
+            matches,
+            "expected steps {:?}, but the workflow executed {:?}",
+            expected, self.steps
+        );
+    }
+}
+
+fn step_matches(name: &str, expected: &str) -> bool {
+    name == expected
+        || name
+            .strip_suffix(expected)
+            .is_some_and(|prefix| prefix.ends_with("::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::step_matches;
+
+    #[test]
+    fn step_matches_full_and_short_names() {
+        assert!(step_matches("app::steps::Greet", "app::steps::Greet"));
+        assert!(step_matches("app::steps::Greet", "Greet"));
+        assert!(!step_matches("app::steps::PoliteGreet", "Greet"));
+        assert!(!step_matches("app::steps::Greet", "Wave"));
+    }
+}
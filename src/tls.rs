@@ -0,0 +1,161 @@
+use std::fs::read;
+use std::future::Future;
+use std::net::TcpListener;
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::extract::Host;
+use axum::handler::Handler;
+use axum::http::Uri;
+use axum::response::Redirect;
+use axum::{Extension, Router};
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+
+use crate::config::TlsConfig;
+use crate::reload::POLL_INTERVAL;
+use crate::shutdown::{run_until_shutdown, AbandonedDelivery, Deliveries};
+use crate::tasks::BackgroundTasks;
+use crate::Error;
+
+/// Serves the app over HTTPS until the shutdown future resolves
+///
+/// The redirect from HTTP to HTTPS is served on its own listener, and shuts down with the app.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    app: Router,
+    tls: &TlsConfig,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    deliveries: &Deliveries,
+    timeout: Duration,
+) -> Result<Vec<AbandonedDelivery>, Error> {
+    let https_port = listener
+        .local_addr()
+        .context("failed to get socket address from TCP listener")?
+        .port();
+
+    let rustls = RustlsConfig::from_pem_file(&tls.certificate_path, &tls.key_path)
+        .await
+        .map_err(|error| {
+            Error::Configuration(format!("failed to load TLS certificate and key: {}", error))
+        })?;
+
+    let handle = Handle::new();
+
+    // The certificate watcher and the redirect stop together with the server
+    let mut tasks = BackgroundTasks::default();
+
+    if let Some(address) = tls.redirect_address {
+        let listener = TcpListener::bind(address).context("failed to bind TLS redirect address")?;
+        let redirect = axum_server::from_tcp(listener)
+            .handle(handle.clone())
+            .serve(redirect_router(https_port).into_make_service());
+
+        tasks.spawn(async move {
+            if let Err(error) = redirect.await {
+                tracing::error!(%error, "failed to serve redirect from HTTP to HTTPS");
+            }
+        });
+    }
+
+    tasks.spawn(watch(rustls.clone(), tls.clone()));
+
+    let graceful = handle.clone();
+    tasks.spawn(async move {
+        shutdown.await;
+        graceful.graceful_shutdown(None);
+    });
+
+    let server = axum_server::from_tcp_rustls(listener, rustls)
+        .handle(handle.clone())
+        .serve(app.into_make_service());
+
+    let result = run_until_shutdown(server, deliveries, timeout).await;
+    handle.shutdown();
+    drop(tasks);
+
+    result
+}
+
+/// Reloads the certificate chain and key when their files change
+///
+/// If the new files cannot be loaded, the old certificate is kept and the error is logged.
+async fn watch(rustls: RustlsConfig, tls: TlsConfig) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut files = read_files(&tls);
+
+    loop {
+        interval.tick().await;
+
+        let current = read_files(&tls);
+        if current == files {
+            continue;
+        }
+        files = current;
+
+        match rustls
+            .reload_from_pem_file(&tls.certificate_path, &tls.key_path)
+            .await
+        {
+            Ok(()) => tracing::info!("reloaded TLS certificate"),
+            Err(error) => {
+                tracing::error!(%error, "failed to reload TLS certificate, keeping the old one")
+            }
+        }
+    }
+}
+
+fn read_files(tls: &TlsConfig) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+    (read(&tls.certificate_path).ok(), read(&tls.key_path).ok())
+}
+
+fn redirect_router(https_port: u16) -> Router {
+    Router::new()
+        .fallback(redirect.into_service())
+        .layer(Extension(https_port))
+}
+
+async fn redirect(Host(host): Host, uri: Uri, Extension(https_port): Extension<u16>) -> Redirect {
+    Redirect::permanent(&https_uri(&host, &uri, https_port))
+}
+
+fn https_uri(host: &str, uri: &Uri, https_port: u16) -> String {
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    };
+
+    let path = uri
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+
+    match https_port {
+        443 => format!("https://{}{}", host, path),
+        port => format!("https://{}:{}{}", host, port, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Uri;
+
+    use super::https_uri;
+
+    #[test]
+    fn https_uri_replaces_port() {
+        let uri = Uri::from_static("/health?verbose=1");
+
+        assert_eq!(
+            "https://octox.dev:8443/health?verbose=1",
+            https_uri("octox.dev:8080", &uri, 8443)
+        );
+    }
+
+    #[test]
+    fn https_uri_omits_default_port() {
+        let uri = Uri::from_static("/");
+
+        assert_eq!("https://octox.dev/", https_uri("octox.dev", &uri, 443));
+    }
+}
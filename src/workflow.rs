@@ -6,7 +6,7 @@ use axum::response::{IntoResponse, Response};
 use github_parts::event::Event;
 use thiserror::Error;
 
-use crate::State;
+use crate::{Delivery, State};
 
 #[async_trait]
 pub trait Workflow: Debug + Sync + Send {
@@ -17,22 +17,67 @@ pub trait Workflow: Debug + Sync + Send {
     fn initial_step(&self) -> Box<dyn Step>;
 
     async fn execute(&self, event: Event) -> Result<serde_json::Value, WorkflowError> {
-        let mut step = self.initial_step();
         let mut state = self.initial_state();
+        state.insert(event);
+
+        self.run(state).await
+    }
 
+    /// Executes the workflow for a webhook delivery
+    ///
+    /// This is how the webhook route invokes workflows. In addition to the event, the state
+    /// contains the [`Delivery`] with the delivery's metadata and its raw payload.
+    async fn execute_delivery(
+        &self,
+        event: Event,
+        delivery: Delivery,
+    ) -> Result<serde_json::Value, WorkflowError> {
+        let mut state = self.initial_state();
         state.insert(event);
+        state.insert(delivery);
 
-        loop {
-            step = match step.next(&mut state).await? {
-                Transition::Next(step) => step,
-                Transition::Complete(result) => return Ok(result),
-            }
+        self.run(state).await
+    }
+
+    async fn run(&self, mut state: State) -> Result<serde_json::Value, WorkflowError> {
+        run_steps(self.initial_step(), &mut state, |_| {}).await
+    }
+}
+
+/// Executes the steps until one of them completes the workflow or fails
+///
+/// The name of each step is passed to `on_step` before the step is executed.
+pub(crate) async fn run_steps(
+    mut step: Box<dyn Step>,
+    state: &mut State,
+    mut on_step: impl FnMut(&'static str) + Send,
+) -> Result<serde_json::Value, WorkflowError> {
+    loop {
+        let name = step.name();
+        on_step(name);
+
+        #[cfg(feature = "metrics")]
+        let started_at = std::time::Instant::now();
+
+        let transition = step.next(state).await;
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::Metrics::global().record_step(name, started_at.elapsed());
+
+        step = match transition? {
+            Transition::Next(step) => step,
+            Transition::Complete(result) => return Ok(result),
         }
     }
 }
 
 #[async_trait]
 pub trait Step: Send + Sync {
+    /// Name of the step in metrics, which defaults to the name of its type
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
     async fn next(self: Box<Self>, state: &mut State) -> Result<Transition, WorkflowError>;
 }
 
@@ -41,6 +86,23 @@ pub enum Transition {
     Complete(serde_json::Value),
 }
 
+/// Reason why a workflow ended without completing
+///
+/// Steps can end a workflow early by returning an error. The error determines the status code of
+/// the response to the webhook delivery, which decides whether GitHub considers the delivery
+/// failed and whether it can be redelivered:
+///
+/// | Variant           | Status                      | Redelivered |
+/// | ----------------- | --------------------------- | ----------- |
+/// | `Configuration`   | `200 OK`                    | No          |
+/// | `Skipped`         | `202 Accepted`              | No          |
+/// | `MissingData`     | `400 Bad Request`           | No          |
+/// | `Rejected`        | `422 Unprocessable Entity`  | No          |
+/// | `RetryLater`      | `503 Service Unavailable`   | Yes         |
+/// | `UnexpectedError` | `500 Internal Server Error` | Yes         |
+///
+/// The body of the response contains the error's code. Failed deliveries are described by an
+/// `application/problem+json` document, and acknowledged ones by an `application/json` document.
 #[derive(Debug, Error)]
 pub enum WorkflowError {
     #[error("configuration was not valid")]
@@ -49,18 +111,52 @@ pub enum WorkflowError {
     #[error("{0}")]
     MissingData(String),
 
+    /// The delivery is not relevant for the workflow, and has not been processed
+    #[error("skipped: {0}")]
+    Skipped(String),
+
+    /// The delivery cannot be processed, and would fail again when it is redelivered
+    #[error("rejected: {0}")]
+    Rejected(String),
+
+    /// The delivery could not be processed right now, e.g. because a dependency is unavailable
+    #[error("retry later: {0}")]
+    RetryLater(String),
+
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
 
-impl IntoResponse for WorkflowError {
-    fn into_response(self) -> Response {
+impl WorkflowError {
+    /// Stable, machine-readable code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            WorkflowError::Configuration => "invalid_workflow_configuration",
+            WorkflowError::MissingData(_) => "missing_data",
+            WorkflowError::Skipped(_) => "skipped",
+            WorkflowError::Rejected(_) => "rejected",
+            WorkflowError::RetryLater(_) => "retry_later",
+            WorkflowError::UnexpectedError(_) => "unexpected_error",
+        }
+    }
+
+    /// Status code of the response to the webhook delivery
+    ///
+    /// An invalid configuration is not an error on GitHub's side, so the delivery is acknowledged.
+    pub fn status(&self) -> StatusCode {
         match self {
-            WorkflowError::Configuration => StatusCode::OK.into_response(),
-            WorkflowError::MissingData(error) => (StatusCode::BAD_REQUEST, error).into_response(),
-            WorkflowError::UnexpectedError(error) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response()
-            }
+            WorkflowError::Configuration => StatusCode::OK,
+            WorkflowError::MissingData(_) => StatusCode::BAD_REQUEST,
+            WorkflowError::Skipped(_) => StatusCode::ACCEPTED,
+            WorkflowError::Rejected(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WorkflowError::RetryLater(_) => StatusCode::SERVICE_UNAVAILABLE,
+            WorkflowError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
+
+impl IntoResponse for WorkflowError {
+    fn into_response(self) -> Response {
+        crate::Error::from(self).into_response()
+    }
+}
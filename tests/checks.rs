@@ -0,0 +1,91 @@
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
+use mockito::{mock, Matcher};
+use serde_json::json;
+
+use octox::checks::{
+    Annotation, AnnotationLevel, CheckRun, CheckRunAction, Conclusion, Markdown, Output,
+};
+use octox::client::{GitHubClient, GitHubError};
+
+fn client() -> GitHubClient {
+    GitHubClient::new(
+        GitHubHost::new(mockito::server_url()),
+        AppId::new(1),
+        PrivateKey::new(include_str!("fixtures/private-key.pem").into()),
+    )
+}
+
+#[tokio::test]
+async fn check_run_moves_through_states() -> Result<(), GitHubError> {
+    let create = mock("POST", "/repos/devxbots/states/check-runs")
+        .match_body(Matcher::PartialJson(json!({
+            "name": "octox",
+            "head_sha": "ce587453",
+            "status": "queued",
+            "actions": [{ "label": "Fix", "description": "Fix the issues", "identifier": "fix" }]
+        })))
+        .with_status(201)
+        .with_body(r#"{ "id": 4 }"#)
+        .create();
+    let start = mock("PATCH", "/repos/devxbots/states/check-runs/4")
+        .match_body(Matcher::PartialJson(json!({ "status": "in_progress" })))
+        .with_status(200)
+        .with_body("{}")
+        .create();
+    let complete = mock("PATCH", "/repos/devxbots/states/check-runs/4")
+        .match_body(Matcher::PartialJson(json!({
+            "status": "completed",
+            "conclusion": "success",
+            "output": { "title": "Passed", "summary": "## Summary\n\nAll good" }
+        })))
+        .with_status(200)
+        .with_body("{}")
+        .create();
+
+    let actions = [CheckRunAction::new("Fix", "Fix the issues", "fix")];
+    let check_run =
+        CheckRun::create(&client(), "devxbots/states", "octox", "ce587453", &actions).await?;
+
+    check_run.start().await?;
+
+    let summary = Markdown::new().heading(2, "Summary").paragraph("All good");
+    check_run
+        .complete(Conclusion::Success, &Output::new("Passed", summary))
+        .await?;
+
+    create.assert();
+    start.assert();
+    complete.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn check_run_sends_annotations_in_batches() -> Result<(), GitHubError> {
+    let annotations: Vec<Annotation> = (1..=120)
+        .map(|line| {
+            Annotation::new(
+                "src/lib.rs",
+                line,
+                line,
+                AnnotationLevel::Warning,
+                "warning",
+            )
+        })
+        .collect();
+
+    let batches = mock("PATCH", "/repos/devxbots/batches/check-runs/4")
+        .match_body(Matcher::Regex("src/lib.rs".into()))
+        .with_status(200)
+        .with_body("{}")
+        .expect(3)
+        .create();
+
+    let check_run = CheckRun::from_id(&client(), "devxbots/batches", 4);
+    let output = Output::new("Warnings", "Found 120 warnings").annotations(annotations);
+
+    check_run.complete(Conclusion::Neutral, &output).await?;
+
+    batches.assert();
+    Ok(())
+}
@@ -0,0 +1,125 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use octox::testing::MockGitHub;
+use octox::Error;
+
+fn command(github_host: &str, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_octox"));
+    command
+        .args(args)
+        .env("OCTOX_GITHUB_HOST", github_host)
+        .env("OCTOX_APP_ID", "1")
+        .env("OCTOX_PRIVATE_KEY_PATH", "tests/fixtures/private-key.pem")
+        .env("OCTOX_WEBHOOK_SECRET", "secret")
+        .env_remove("OCTOX_PRIVATE_KEY")
+        .env_remove("OCTOX_WEBHOOK_SECRET_PATH");
+    command
+}
+
+fn octox(github_host: &str, args: &[&str]) -> Output {
+    command(github_host, args).output().unwrap()
+}
+
+/// Runs the tool without blocking the runtime, which serves the mock GitHub API
+async fn octox_async(github_host: &str, args: &[&str]) -> Output {
+    tokio::process::Command::from(command(github_host, args))
+        .output()
+        .await
+        .unwrap()
+}
+
+#[test]
+fn sign_prints_signature_of_file() {
+    let output = octox(
+        "https://api.github.com",
+        &["sign", "tests/fixtures/check_run.created.json"],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        "sha256=ba9f77aa6bc9740e9be7f68e4e21a64821cc5b59fd286d409d605a0b8affe7ff\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn check_config_reports_problems() {
+    let output = octox("not a url", &["check-config"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("github host must be an HTTP or HTTPS URL"));
+}
+
+#[test]
+fn check_config_accepts_valid_config() {
+    let output = octox("https://api.github.com", &["check-config"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("configuration is valid"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deliveries_list_and_redeliver_call_github() -> Result<(), Error> {
+    let github = MockGitHub::start()?;
+    github
+        .paginate(
+            "/app/hook/deliveries",
+            vec![
+                json!({ "id": 3, "guid": "c", "delivered_at": "2022-10-03T00:00:00Z", "status_code": 422, "event": "check_run", "action": "created" }),
+                json!({ "id": 2, "guid": "b", "delivered_at": "2022-10-02T00:00:00Z", "status_code": 500, "event": "check_run", "action": "created" }),
+                json!({ "id": 1, "guid": "a", "delivered_at": "2022-10-01T00:00:00Z", "status_code": 200, "event": "push", "action": null }),
+            ],
+            100,
+        )
+        .respond(
+            Method::POST,
+            "/app/hook/deliveries/2/attempts",
+            StatusCode::ACCEPTED,
+            json!({}),
+        );
+
+    let list = octox_async(&github.url(), &["deliveries", "list", "--failed"]).await;
+    assert!(list.status.success());
+    assert_eq!(
+        "2\tb\t2022-10-02T00:00:00Z\t500\tcheck_run.created\n",
+        String::from_utf8_lossy(&list.stdout)
+    );
+
+    let redeliver = octox_async(&github.url(), &["deliveries", "redeliver", "2"]).await;
+    assert!(redeliver.status.success());
+    assert_eq!(
+        1,
+        github
+            .requests_to(Method::POST, "/app/hook/deliveries/2/attempts")
+            .len()
+    );
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deliveries_list_scans_recent_deliveries_only() -> Result<(), Error> {
+    let github = MockGitHub::start()?;
+    let deliveries = (0..1500)
+        .rev()
+        .map(|id| json!({ "id": id, "guid": id.to_string(), "delivered_at": "2022-10-01T00:00:00Z", "status_code": 200, "event": "push", "action": null }))
+        .collect();
+    github.paginate("/app/hook/deliveries", deliveries, 100);
+
+    let list = octox_async(&github.url(), &["deliveries", "list", "--failed"]).await;
+
+    assert!(list.status.success());
+    assert!(list.stdout.is_empty());
+    assert_eq!(
+        10,
+        github
+            .requests_to(Method::GET, "/app/hook/deliveries")
+            .len()
+    );
+    Ok(())
+}
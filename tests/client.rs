@@ -0,0 +1,350 @@
+use std::time::Duration;
+
+use futures::TryStreamExt;
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
+use mockito::{mock, Matcher};
+use serde_json::{json, Value};
+
+use octox::client::{GitHubClient, GitHubError};
+
+fn client() -> GitHubClient {
+    GitHubClient::new(
+        GitHubHost::new(mockito::server_url()),
+        AppId::new(1),
+        PrivateKey::new(include_str!("fixtures/private-key.pem").into()),
+    )
+    .max_wait(Duration::from_secs(0))
+}
+
+#[tokio::test]
+async fn client_authenticates_as_installation() -> Result<(), GitHubError> {
+    let token = mock("POST", "/app/installations/42/access_tokens")
+        .with_status(201)
+        .with_body(r#"{ "token": "installation-token", "expires_at": "2099-01-01T00:00:00Z" }"#)
+        .expect(1)
+        .create();
+    let installation = mock("GET", "/installation/repositories")
+        .match_header("Authorization", "Bearer installation-token")
+        .with_status(200)
+        .with_body("{}")
+        .expect(2)
+        .create();
+
+    let client = client().installation(42);
+    client.get::<Value>("installation/repositories").await?;
+    client.get::<Value>("installation/repositories").await?;
+
+    token.assert();
+    installation.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_paginates_through_link_header() -> Result<(), GitHubError> {
+    let _first = mock("GET", "/paginate/items")
+        .with_status(200)
+        .with_header(
+            "Link",
+            &format!(
+                "<{}/paginate/items?page=2>; rel=\"next\"",
+                mockito::server_url()
+            ),
+        )
+        .with_body("[1, 2]")
+        .create();
+    let _second = mock("GET", "/paginate/items?page=2")
+        .with_status(200)
+        .with_body("[3]")
+        .create();
+
+    let items: Vec<u64> = client()
+        .paginate::<u64>("paginate/items")
+        .try_collect()
+        .await?;
+
+    assert_eq!(vec![1, 2, 3], items);
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_sends_etag_in_conditional_request() -> Result<(), GitHubError> {
+    let _cached = mock("GET", "/conditional")
+        .with_status(200)
+        .with_header("ETag", "\"etag\"")
+        .with_body(r#"{ "cached": true }"#)
+        .expect(1)
+        .create();
+
+    let client = client();
+    client.get::<Value>("conditional").await?;
+
+    let not_modified = mock("GET", "/conditional")
+        .match_header("If-None-Match", "\"etag\"")
+        .with_status(304)
+        .create();
+
+    let response: Value = client.get("conditional").await?;
+
+    assert_eq!(Value::Bool(true), response["cached"]);
+    not_modified.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_paginates_through_cached_pages() -> Result<(), GitHubError> {
+    let _first = mock("GET", "/cached-pages")
+        .with_status(200)
+        .with_header("ETag", "\"first\"")
+        .with_header(
+            "Link",
+            &format!(
+                "<{}/cached-pages?page=2>; rel=\"next\"",
+                mockito::server_url()
+            ),
+        )
+        .with_body("[1, 2]")
+        .expect(1)
+        .create();
+    let _second = mock("GET", "/cached-pages?page=2")
+        .with_status(200)
+        .with_header("ETag", "\"second\"")
+        .with_body("[3]")
+        .expect(1)
+        .create();
+
+    let client = client();
+    let items: Vec<u64> = client.paginate::<u64>("cached-pages").try_collect().await?;
+    assert_eq!(vec![1, 2, 3], items);
+
+    let first_not_modified = mock("GET", "/cached-pages")
+        .match_header("If-None-Match", "\"first\"")
+        .with_status(304)
+        .expect(1)
+        .create();
+    let second_not_modified = mock("GET", "/cached-pages?page=2")
+        .match_header("If-None-Match", "\"second\"")
+        .with_status(304)
+        .expect(1)
+        .create();
+
+    let items: Vec<u64> = client.paginate::<u64>("cached-pages").try_collect().await?;
+
+    assert_eq!(vec![1, 2, 3], items);
+    first_not_modified.assert();
+    second_not_modified.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_retries_after_secondary_rate_limit() -> Result<(), GitHubError> {
+    let rate_limited = mock("GET", "/rate-limited")
+        .with_status(403)
+        .with_header("Retry-After", "0")
+        .with_body(r#"{ "message": "You have exceeded a secondary rate limit." }"#)
+        .expect(1)
+        .create();
+    let _success = mock("GET", "/rate-limited")
+        .with_status(200)
+        .with_body("{}")
+        .create();
+
+    client().get::<Value>("rate-limited").await?;
+
+    rate_limited.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_retries_idempotent_request_after_server_error() -> Result<(), GitHubError> {
+    let server_error = mock("GET", "/flaky").with_status(502).expect(1).create();
+    let _success = mock("GET", "/flaky")
+        .with_status(200)
+        .with_body("{}")
+        .create();
+
+    client().get::<Value>("flaky").await?;
+
+    server_error.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_does_not_retry_server_error_before_retry_after() -> Result<(), GitHubError> {
+    let unavailable = mock("GET", "/unavailable")
+        .with_status(503)
+        .with_header("Retry-After", "120")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let result = client().get::<Value>("unavailable").await;
+
+    assert!(matches!(result, Err(GitHubError::Status { status, .. }) if status == 503));
+    unavailable.assert();
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_does_not_retry_post_after_server_error() {
+    let server_error = mock("POST", "/check-runs")
+        .with_status(502)
+        .expect(1)
+        .create();
+
+    let error = client()
+        .post::<_, Value>("check-runs", &json!({ "name": "octox" }))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, GitHubError::Status { .. }));
+    server_error.assert();
+}
+
+#[tokio::test]
+async fn client_fails_when_rate_limit_resets_too_late() {
+    let _rate_limited = mock("GET", "/exhausted")
+        .with_status(403)
+        .with_header("x-ratelimit-remaining", "0")
+        .with_header("x-ratelimit-reset", "9999999999")
+        .create();
+
+    let error = client().get::<Value>("exhausted").await.unwrap_err();
+
+    assert!(matches!(error, GitHubError::RateLimited(_)));
+}
+
+#[tokio::test]
+async fn client_returns_error_message_from_github() {
+    let _not_found = mock("GET", Matcher::Exact("/not-found".into()))
+        .with_status(404)
+        .with_body(r#"{ "message": "Not Found" }"#)
+        .create();
+
+    let error = client().get::<Value>("not-found").await.unwrap_err();
+
+    assert_eq!(
+        "GitHub responded with 404 Not Found: Not Found",
+        error.to_string()
+    );
+}
+
+#[tokio::test]
+async fn graphql_returns_typed_data() -> Result<(), GitHubError> {
+    let _graphql = mock("POST", "/graphql")
+        .match_body(Matcher::PartialJson(
+            json!({ "variables": { "owner": "devxbots" } }),
+        ))
+        .with_status(200)
+        .with_body(r#"{ "data": { "repository": { "name": "octox" } } }"#)
+        .create();
+
+    let data: Value = client()
+        .graphql(
+            "query($owner: String!) { repository(owner: $owner, name: \"octox\") { name } }",
+            &json!({ "owner": "devxbots" }),
+        )
+        .await?;
+
+    assert_eq!("octox", data["repository"]["name"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_returns_structured_errors() {
+    let _graphql = mock("POST", "/graphql")
+        .match_body(Matcher::Regex("missing".into()))
+        .with_status(200)
+        .with_body(
+            r#"{ "data": null, "errors": [{ "type": "NOT_FOUND", "path": ["repository"], "message": "Could not resolve to a Repository" }] }"#,
+        )
+        .create();
+
+    let error = client()
+        .graphql::<_, Value>("query { missing }", &json!({}))
+        .await
+        .unwrap_err();
+
+    match error {
+        GitHubError::GraphQL(errors) => {
+            assert_eq!(Some("NOT_FOUND".into()), errors[0].kind);
+        }
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn graphql_paginate_follows_cursor() -> Result<(), GitHubError> {
+    let _first = mock("POST", "/graphql")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex("items".into()),
+            Matcher::PartialJson(json!({ "variables": { "cursor": null } })),
+        ]))
+        .with_status(200)
+        .with_body(
+            r#"{ "data": { "viewer": { "items": { "nodes": [1, 2], "pageInfo": { "hasNextPage": true, "endCursor": "abc" } } } } }"#,
+        )
+        .create();
+    let _second = mock("POST", "/graphql")
+        .match_body(Matcher::PartialJson(json!({ "variables": { "cursor": "abc" } })))
+        .with_status(200)
+        .with_body(
+            r#"{ "data": { "viewer": { "items": { "nodes": [3], "pageInfo": { "hasNextPage": false, "endCursor": "def" } } } } }"#,
+        )
+        .create();
+
+    let client = client();
+    let nodes: Vec<u64> = client
+        .graphql_paginate::<u64>(
+            "query($cursor: String) { viewer { items(after: $cursor) { nodes } } }",
+            json!({}),
+            &["viewer", "items"],
+        )
+        .try_collect()
+        .await?;
+
+    assert_eq!(vec![1, 2, 3], nodes);
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_paginate_accepts_null_variables() -> Result<(), GitHubError> {
+    let _page = mock("POST", "/graphql")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex("organizations".into()),
+            Matcher::PartialJson(json!({ "variables": { "cursor": null } })),
+        ]))
+        .with_status(200)
+        .with_body(
+            r#"{ "data": { "viewer": { "organizations": { "nodes": [1], "pageInfo": { "hasNextPage": false, "endCursor": null } } } } }"#,
+        )
+        .create();
+
+    let client = client();
+    let nodes: Vec<u64> = client
+        .graphql_paginate::<u64>(
+            "query($cursor: String) { viewer { organizations(after: $cursor) { nodes } } }",
+            json!(null),
+            &["viewer", "organizations"],
+        )
+        .try_collect()
+        .await?;
+
+    assert_eq!(vec![1], nodes);
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_paginate_rejects_variables_that_are_not_an_object() {
+    let client = client();
+    let result: Result<Vec<u64>, GitHubError> = client
+        .graphql_paginate::<u64>(
+            "query($cursor: String) { viewer { items(after: $cursor) { nodes } } }",
+            json!(["devxbots"]),
+            &["viewer", "items"],
+        )
+        .try_collect()
+        .await;
+
+    assert!(matches!(result, Err(GitHubError::InvalidVariables(_))));
+}
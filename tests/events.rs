@@ -0,0 +1,115 @@
+#![cfg(feature = "testing")]
+
+use axum::http::StatusCode;
+use github_parts::event::Event;
+use github_parts::github::WebhookSecret;
+use serde_json::json;
+use tower::ServiceExt;
+
+use octox::testing::{
+    sign, CheckRunEventBuilder, CheckSuiteEventBuilder, EventBuilder, IssueCommentEventBuilder,
+    PullRequestEventBuilder, PushEventBuilder, WorkflowHarness,
+};
+use octox::{Error, Octox};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+#[test]
+fn check_run_builder_generates_check_run_event() -> Result<(), Error> {
+    let fixture = CheckRunEventBuilder::rerequested()
+        .repo("devxbots/octox")
+        .installation(42)
+        .head_sha("ce587453")
+        .fixture();
+
+    assert!(matches!(fixture.event()?, Event::CheckRun(_)));
+
+    let delivery = fixture.delivery()?;
+    assert_eq!("check_run", delivery.event);
+    assert_eq!(Some("rerequested"), delivery.action());
+    assert_eq!(Some(42), delivery.installation());
+    assert_eq!(Some("devxbots/octox"), delivery.repository());
+    assert_eq!("ce587453", delivery.payload["check_run"]["head_sha"]);
+    Ok(())
+}
+
+#[test]
+fn pull_request_builder_overrides_fields() {
+    let payload = PullRequestEventBuilder::opened()
+        .repo("o/r")
+        .number(5)
+        .draft(true)
+        .field("/pull_request/labels", json!([{ "name": "bug" }]))
+        .build();
+
+    assert_eq!("opened", payload["action"]);
+    assert_eq!(5, payload["number"]);
+    assert_eq!("o/r", payload["repository"]["full_name"]);
+    assert_eq!("o", payload["repository"]["owner"]["login"]);
+    assert_eq!(true, payload["pull_request"]["draft"]);
+    assert_eq!("bug", payload["pull_request"]["labels"][0]["name"]);
+}
+
+#[test]
+fn builders_set_event_type_and_action() {
+    let builders: Vec<(Box<dyn EventBuilder>, &str, Option<&str>)> = vec![
+        (
+            Box::new(CheckSuiteEventBuilder::requested()),
+            "check_suite",
+            Some("requested"),
+        ),
+        (
+            Box::new(IssueCommentEventBuilder::created().on_pull_request()),
+            "issue_comment",
+            Some("created"),
+        ),
+        (Box::new(PushEventBuilder::new()), "push", None),
+    ];
+
+    for (builder, event_type, action) in builders {
+        let payload = builder.build();
+
+        assert_eq!(event_type, builder.event_type());
+        assert_eq!(action, payload["action"].as_str());
+        assert_eq!(1, payload["installation"]["id"]);
+    }
+}
+
+#[test]
+fn sign_matches_signature_of_fixture() -> Result<(), Error> {
+    let secret = WebhookSecret::new("secret".into());
+    let fixture = PushEventBuilder::new().fixture();
+
+    assert_eq!(
+        sign(fixture.payload(), &secret)?,
+        fixture.signature(&secret)?
+    );
+    assert!(fixture.signature(&secret)?.starts_with("sha256="));
+    Ok(())
+}
+
+#[tokio::test]
+async fn generated_events_are_accepted_by_router() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let router = Octox::new()
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?
+        .router()?;
+
+    let request = PullRequestEventBuilder::opened()
+        .fixture()
+        .signed_request("/", &WebhookSecret::new("secret".into()))?;
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+
+    let run = WorkflowHarness::new(&HelloWorld)
+        .fixture(&CheckRunEventBuilder::created().fixture())?
+        .run()
+        .await;
+    run.assert_completed_with(json!("received check run event"));
+    Ok(())
+}
@@ -0,0 +1,183 @@
+#![cfg(feature = "testing")]
+
+use anyhow::Context;
+use async_trait::async_trait;
+use axum::http::{Method, StatusCode};
+use futures::TryStreamExt;
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey, WebhookSecret};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use octox::checks::{Annotation, AnnotationLevel, CheckRun, Conclusion, Output};
+use octox::client::GitHubClient;
+use octox::testing::{Fixture, MockGitHub};
+use octox::{Delivery, Error, Octox, State, Step, Transition, Workflow, WorkflowError};
+
+fn client(github: &MockGitHub) -> GitHubClient {
+    GitHubClient::new(
+        GitHubHost::new(github.url()),
+        AppId::new(1),
+        PrivateKey::new(include_str!("fixtures/private-key.pem").into()),
+    )
+}
+
+#[derive(Debug)]
+struct Lint {
+    github_client: GitHubClient,
+}
+
+impl Lint {
+    fn constructor(
+        github_host: GitHubHost,
+        app_id: AppId,
+        private_key: PrivateKey,
+    ) -> Box<dyn Workflow> {
+        Box::new(Lint {
+            github_client: GitHubClient::new(github_host, app_id, private_key),
+        })
+    }
+}
+
+#[async_trait]
+impl Workflow for Lint {
+    fn initial_state(&self) -> State {
+        let mut state = State::new();
+        state.insert(self.github_client.clone());
+        state
+    }
+
+    fn initial_step(&self) -> Box<dyn Step> {
+        Box::new(ReportWarnings)
+    }
+}
+
+struct ReportWarnings;
+
+#[async_trait]
+impl Step for ReportWarnings {
+    async fn next(self: Box<Self>, state: &mut State) -> Result<Transition, WorkflowError> {
+        let delivery: &Delivery = state.get().context("failed to get delivery")?;
+        let installation = delivery.installation().context("missing installation")?;
+        let repository = delivery.repository().context("missing repository")?;
+        let github_client: &GitHubClient = state.get().context("failed to get client")?;
+        let github_client = github_client.installation(installation);
+
+        let check_run = CheckRun::create(&github_client, repository, "lint", "ec26c3e5", &[])
+            .await
+            .context("failed to create check run")?;
+        let output = Output::new("Warnings", "Found 1 warning").annotation(Annotation::new(
+            "src/lib.rs",
+            1,
+            1,
+            AnnotationLevel::Warning,
+            "unused import",
+        ));
+        check_run
+            .complete(Conclusion::Neutral, &output)
+            .await
+            .context("failed to complete check run")?;
+
+        Ok(Transition::Complete(json!(check_run.id())))
+    }
+}
+
+#[tokio::test]
+async fn mock_github_records_check_runs_of_workflow() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let github = MockGitHub::start()?;
+    let router = Octox::new()
+        .github_host(github.url())?
+        .webhook_secret("secret")?
+        .workflow(Lint::constructor)?
+        .router()?;
+
+    let mut payload: Value = serde_json::from_str(include_str!("fixtures/check_run.created.json"))?;
+    payload["installation"] = json!({ "id": 42 });
+
+    let fixture = Fixture::new("not_a_real_event", payload.to_string());
+    let request = fixture.signed_request("/", &WebhookSecret::new("secret".into()))?;
+    let response = router.oneshot(request).await.unwrap();
+
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        1,
+        github
+            .requests_to(Method::POST, "/app/installations/42/access_tokens")
+            .len()
+    );
+
+    let created = github.requests_to(Method::POST, "/repos/Codertocat/Hello-World/check-runs");
+    assert_eq!(1, created.len());
+    assert_eq!(
+        Some("Bearer installation-token-42"),
+        created[0]
+            .headers
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+    );
+
+    let check_runs = github.check_runs();
+    assert_eq!(1, check_runs.len());
+    assert_eq!("completed", check_runs[0]["status"]);
+    assert_eq!(
+        "unused import",
+        check_runs[0]["output"]["annotations"][0]["message"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_github_paginates_lists() -> Result<(), Error> {
+    let github = MockGitHub::start()?;
+    github.paginate(
+        "/app/installations",
+        (1..=5).map(|id| json!(id)).collect(),
+        2,
+    );
+
+    let items: Vec<u64> = client(&github)
+        .paginate::<u64>("app/installations?per_page=2")
+        .try_collect()
+        .await?;
+
+    assert_eq!(vec![1, 2, 3, 4, 5], items);
+    assert_eq!(
+        3,
+        github.requests_to(Method::GET, "/app/installations").len()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn mock_github_serves_app_files_and_custom_responses() -> Result<(), Error> {
+    let github = MockGitHub::start()?;
+    github
+        .file("devxbots/octox", ".github/octox.yml", "enabled: true")
+        .respond(
+            Method::GET,
+            "/rate_limit",
+            StatusCode::OK,
+            json!({ "resources": {} }),
+        );
+
+    let client = client(&github);
+
+    let app: Value = client.get("app").await?;
+    assert_eq!("octox", app["slug"]);
+
+    let file: Value = client
+        .get("repos/devxbots/octox/contents/.github/octox.yml")
+        .await?;
+    assert_eq!("ZW5hYmxlZDogdHJ1ZQ==", file["content"]);
+
+    let rate_limit: Value = client.get("rate_limit").await?;
+    assert_eq!(json!({ "resources": {} }), rate_limit);
+
+    assert!(client
+        .get::<Value>("repos/devxbots/octox/contents/missing")
+        .await
+        .is_err());
+    Ok(())
+}
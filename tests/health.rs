@@ -1,7 +1,9 @@
 use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
 
-use mockito::mock;
+use mockito::{mock, Matcher};
 use reqwest::Client;
+use serde_json::Value;
 
 use octox::{Error, Octox};
 
@@ -68,3 +70,244 @@ async fn health_returns_error() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn health_shares_cached_github_check_with_readiness() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let mock = mock("GET", "/app").with_status(200).expect(1).create();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .readiness_cache_ttl(Duration::from_secs(60))?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    for path in ["health", "health", "readyz"] {
+        let response = Client::new()
+            .get(format!("http://{}/{}", addr, path))
+            .send()
+            .await
+            .expect("failed to execute request");
+
+        assert!(response.status().is_success());
+    }
+
+    mock.assert();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn liveness_does_not_call_github() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let mock = mock("GET", "/app").with_status(200).expect(0).create();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let response = Client::new()
+        .get(format!("http://{}/livez", addr))
+        .send()
+        .await
+        .expect("failed to execute request");
+
+    assert!(response.status().is_success());
+    mock.assert();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn readiness_caches_github_check() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let mock = mock("GET", "/app").with_status(200).expect(1).create();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .readiness_cache_ttl(Duration::from_secs(60))?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    for _ in 0..3 {
+        let response = Client::new()
+            .get(format!("http://{}/readyz", addr))
+            .send()
+            .await
+            .expect("failed to execute request");
+
+        assert!(response.status().is_success());
+
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(true, body["ready"]);
+        assert_eq!("ok", body["components"]["github"]["status"]);
+        assert_eq!(0, body["components"]["queue"]["depth"]);
+    }
+
+    mock.assert();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn readiness_does_not_retry_github_check() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let mock = mock("GET", "/app").with_status(502).expect(1).create();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    // Retrying the server error would take at least one second
+    let response = tokio::time::timeout(
+        Duration::from_millis(900),
+        Client::new().get(format!("http://{}/readyz", addr)).send(),
+    )
+    .await
+    .expect("readiness check was retried")
+    .expect("failed to execute request");
+
+    assert_eq!(503, response.status().as_u16());
+
+    let body: Value = response.json().await.unwrap();
+    assert_eq!("error", body["components"]["github"]["status"]);
+
+    mock.assert();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn diagnostics_reports_app_and_rate_limits() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let _app = mock("GET", "/app")
+        .with_status(200)
+        .with_body(
+            r#"{ "id": 1, "slug": "octox", "name": "octox", "owner": { "login": "devxbots" } }"#,
+        )
+        .create();
+    let _installations = mock("GET", "/app/installations?per_page=1")
+        .with_status(200)
+        .with_body(r#"[{ "id": 7 }]"#)
+        .create();
+    let _token = mock("POST", "/app/installations/7/access_tokens")
+        .with_status(201)
+        .with_body(r#"{ "token": "installation-token", "expires_at": "2099-01-01T00:00:00Z" }"#)
+        .create();
+    // Like GitHub, the mock only reports rate limits to installations
+    let _rate_limit_for_app = mock("GET", "/rate_limit")
+        .match_header("Authorization", Matcher::Regex("^Bearer ey".into()))
+        .with_status(401)
+        .with_body(r#"{ "message": "A JSON web token could not be decoded" }"#)
+        .create();
+    let _rate_limit = mock("GET", "/rate_limit")
+        .match_header("Authorization", "Bearer installation-token")
+        .with_status(200)
+        .with_body(
+            r#"{ "resources": {
+                "core": { "limit": 5000, "remaining": 4990, "used": 10, "reset": 1650000000 },
+                "graphql": { "limit": 5000, "remaining": 5000, "used": 0, "reset": 1650000000 }
+            } }"#,
+        )
+        .create();
+    // Retrying the server error would delay the report
+    let meta = mock("GET", "/meta")
+        .with_status(502)
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .app_id(1)?
+        .github_host(mockito::server_url())?
+        .private_key(include_str!("fixtures/private-key.pem"))?
+        .diagnostics_path("/internal/diagnostics")?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let diagnostics: Value = Client::new()
+        .get(format!("http://{}/internal/diagnostics", addr))
+        .send()
+        .await
+        .expect("failed to execute request")
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!("octox", diagnostics["app"]["slug"]);
+    assert_eq!("devxbots", diagnostics["app"]["owner"]);
+    assert_eq!(7, diagnostics["rate_limit"]["installation"]);
+    assert_eq!(4990, diagnostics["rate_limit"]["core"]["remaining"]);
+    assert_eq!("v3", diagnostics["github"]["api_version"]);
+    meta.assert();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn diagnostics_is_disabled_by_default() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let response = Client::new()
+        .get(format!("http://{}/diagnostics", addr))
+        .send()
+        .await
+        .expect("failed to execute request");
+
+    assert_eq!(404, response.status().as_u16());
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "metrics")]
+
+use std::fs::read;
+use std::net::{SocketAddr, TcpListener};
+
+use reqwest::Client;
+
+use octox::{Error, Octox};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+#[tokio::test]
+async fn metrics_reports_deliveries_and_signature_failures() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let fixture = format!(
+        "{}/tests/fixtures/check_run.created.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let body = read(fixture).unwrap();
+
+    for signature in [
+        "sha256=ba9f77aa6bc9740e9be7f68e4e21a64821cc5b59fd286d409d605a0b8affe7ff",
+        "sha256=0000000000000000000000000000000000000000000000000000000000000000",
+    ] {
+        Client::new()
+            .post(format!("http://{}/", addr))
+            .header("X-GitHub-Event", "not_a_real_event")
+            .header("X-Hub-Signature-256", signature)
+            .body(body.clone())
+            .send()
+            .await?;
+    }
+
+    let response = Client::new()
+        .get(format!("http://{}/metrics", addr))
+        .send()
+        .await?;
+
+    assert!(response.status().is_success());
+
+    let metrics = response.text().await.unwrap();
+
+    assert!(metrics.contains(
+        "octox_deliveries_total{event=\"not_a_real_event\",action=\"created\",result=\"success\"} 1"
+    ));
+    assert!(metrics.contains("octox_signature_failures_total{reason=\"invalid_signature\"} 1"));
+    assert!(metrics.contains("octox_workflow_duration_seconds_count{event=\"not_a_real_event\"} 1"));
+    assert!(metrics.contains("octox_queue_depth 0"));
+
+    Ok(())
+}
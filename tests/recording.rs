@@ -0,0 +1,148 @@
+use std::fs::read;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use github_parts::github::WebhookSecret;
+use tower::ServiceExt;
+
+use octox::recording::{RecordedDelivery, Recorder, Replay};
+use octox::{Environment, Error, Octox};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+fn fixture() -> Vec<u8> {
+    let fixture = format!(
+        "{}/tests/fixtures/check_run.created.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    read(fixture).unwrap()
+}
+
+fn recording() -> RecordedDelivery {
+    RecordedDelivery {
+        recorded_at: "2022-06-01T00:00:00+00:00".into(),
+        headers: [
+            ("x-github-event".to_string(), "not_a_real_event".to_string()),
+            ("x-github-delivery".to_string(), "1".to_string()),
+        ]
+        .into(),
+        body: String::from_utf8(fixture()).unwrap(),
+    }
+}
+
+async fn body_text(response: axum::response::Response) -> String {
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn recording_writes_redacted_deliveries() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let directory = std::env::temp_dir().join("octox-recording-writes-redacted-deliveries");
+    std::fs::remove_dir_all(&directory).ok();
+
+    let router = Octox::new()
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .record_deliveries(Recorder::new(&directory).redact_field("/sender/login"))?
+        .workflow(HelloWorld::constructor)?
+        .router()?;
+
+    let request = Request::post("/")
+        .header("X-GitHub-Event", "not_a_real_event")
+        .header("X-GitHub-Delivery", "72d3162e-cc78-11e3-81ab-4c9367dc0958")
+        .header(
+            "X-Hub-Signature-256",
+            "sha256=ba9f77aa6bc9740e9be7f68e4e21a64821cc5b59fd286d409d605a0b8affe7ff",
+        )
+        .body(Body::from(fixture()))
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+
+    assert_eq!(StatusCode::OK, response.status());
+
+    let recordings = RecordedDelivery::load_all(&directory)?;
+
+    assert_eq!(1, recordings.len());
+    assert_eq!(
+        Some("72d3162e-cc78-11e3-81ab-4c9367dc0958"),
+        recordings[0].delivery_id()
+    );
+    assert!(!recordings[0].headers.contains_key("x-hub-signature-256"));
+    assert!(recordings[0].body.contains("\"login\":\"[REDACTED]\""));
+    Ok(())
+}
+
+#[tokio::test]
+async fn recording_ignores_deliveries_with_invalid_signature() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let directory = std::env::temp_dir().join("octox-recording-ignores-invalid-signature");
+    std::fs::remove_dir_all(&directory).ok();
+
+    let router = Octox::new()
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .record_deliveries(Recorder::new(&directory))?
+        .workflow(HelloWorld::constructor)?
+        .router()?;
+
+    let request = Request::post("/")
+        .header("X-GitHub-Event", "not_a_real_event")
+        .header("X-Hub-Signature-256", "sha256=0000")
+        .body(Body::from(fixture()))
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+    assert!(!directory.exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_signs_deliveries() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let router = Octox::new()
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?
+        .router()?;
+
+    let response = Replay::signed(WebhookSecret::new("secret".into()))
+        .send_to_router(&router, "/", &recording())
+        .await?;
+
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!("\"received unsupported event\"", body_text(response).await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_without_signature_requires_skipped_verification() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let octox = Octox::new()
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?;
+
+    let response = Replay::unsigned()
+        .send_to_router(&octox.router()?, "/", &recording())
+        .await?;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let router = octox
+        .environment(Environment::Development)?
+        .skip_signature_verification()?
+        .router()?;
+
+    let response = Replay::unsigned()
+        .send_to_router(&router, "/", &recording())
+        .await?;
+    assert_eq!(StatusCode::OK, response.status());
+    Ok(())
+}
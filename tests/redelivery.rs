@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
+use mockito::mock;
+use serde_json::json;
+
+use octox::client::{GitHubClient, GitHubError};
+use octox::redelivery::Redelivery;
+
+fn redelivery() -> Redelivery {
+    let client = GitHubClient::new(
+        GitHubHost::new(mockito::server_url()),
+        AppId::new(1),
+        PrivateKey::new(include_str!("fixtures/private-key.pem").into()),
+    );
+
+    Redelivery::new(client).delay(Duration::from_secs(0))
+}
+
+fn hook_delivery(
+    id: u64,
+    guid: &str,
+    status_code: u16,
+    age: chrono::Duration,
+) -> serde_json::Value {
+    json!({
+        "id": id,
+        "guid": guid,
+        "delivered_at": (Utc::now() - age).to_rfc3339(),
+        "redelivery": false,
+        "status_code": status_code,
+        "event": "check_run",
+    })
+}
+
+#[tokio::test]
+async fn redelivery_redelivers_failed_deliveries() -> Result<(), GitHubError> {
+    let _deliveries = mock("GET", "/app/hook/deliveries?per_page=100")
+        .with_status(200)
+        .with_body(
+            json!([
+                hook_delivery(3, "succeeded", 200, chrono::Duration::minutes(1)),
+                hook_delivery(2, "failed", 502, chrono::Duration::minutes(2)),
+                hook_delivery(1, "expired", 502, chrono::Duration::days(4)),
+            ])
+            .to_string(),
+        )
+        .create();
+    let redeliver = mock("POST", "/app/hook/deliveries/2/attempts")
+        .with_status(202)
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let report = redelivery().run().await?;
+
+    redeliver.assert();
+    assert_eq!(vec!["failed".to_string()], report.redelivered);
+    assert_eq!(2, report.scanned);
+    Ok(())
+}
+
+#[tokio::test]
+async fn redelivery_defers_deliveries_over_the_limit() -> Result<(), GitHubError> {
+    let _deliveries = mock("GET", "/app/hook/deliveries?per_page=100")
+        .with_status(200)
+        .with_body(
+            json!([
+                hook_delivery(11, "second", 500, chrono::Duration::minutes(1)),
+                hook_delivery(10, "first", 500, chrono::Duration::minutes(2)),
+            ])
+            .to_string(),
+        )
+        .create();
+    let redeliver = mock("POST", "/app/hook/deliveries/10/attempts")
+        .with_status(202)
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let report = redelivery().max_redeliveries(1).run().await?;
+
+    redeliver.assert();
+    assert_eq!(vec!["first".to_string()], report.redelivered);
+    assert_eq!(1, report.deferred);
+    Ok(())
+}
+
+#[tokio::test]
+async fn redelivery_ignores_deliveries_rejected_by_the_app() -> Result<(), GitHubError> {
+    let _deliveries = mock("GET", "/app/hook/deliveries?per_page=100")
+        .with_status(200)
+        .with_body(
+            json!([
+                hook_delivery(21, "rejected", 422, chrono::Duration::minutes(1)),
+                hook_delivery(20, "timed-out", 0, chrono::Duration::minutes(2)),
+            ])
+            .to_string(),
+        )
+        .create();
+    let rejected = mock("POST", "/app/hook/deliveries/21/attempts")
+        .with_status(202)
+        .with_body("{}")
+        .expect(0)
+        .create();
+    let timed_out = mock("POST", "/app/hook/deliveries/20/attempts")
+        .with_status(202)
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let report = redelivery().run().await?;
+
+    rejected.assert();
+    timed_out.assert();
+    assert_eq!(vec!["timed-out".to_string()], report.redelivered);
+    Ok(())
+}
+
+#[tokio::test]
+async fn redelivery_counts_redeliveries_listed_by_github() -> Result<(), GitHubError> {
+    let mut redelivered = hook_delivery(31, "exhausted", 502, chrono::Duration::minutes(1));
+    redelivered["redelivery"] = json!(true);
+
+    let _deliveries = mock("GET", "/app/hook/deliveries?per_page=100")
+        .with_status(200)
+        .with_body(
+            json!([
+                redelivered,
+                hook_delivery(30, "exhausted", 502, chrono::Duration::minutes(2)),
+            ])
+            .to_string(),
+        )
+        .create();
+    let redeliver = mock("POST", "/app/hook/deliveries/31/attempts")
+        .with_status(202)
+        .with_body("{}")
+        .expect(0)
+        .create();
+
+    let report = redelivery().max_attempts(1).run().await?;
+
+    redeliver.assert();
+    assert!(report.redelivered.is_empty());
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use std::fs::read;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
+use reqwest::Client;
+
+use octox::{
+    Error, ErrorContext, ErrorReporter, Octox, State, Step, Transition, Workflow, WorkflowError,
+};
+
+#[derive(Clone, Debug, Default)]
+struct RecordingReporter(Arc<Mutex<Vec<(String, ErrorContext)>>>);
+
+impl ErrorReporter for RecordingReporter {
+    fn report(&self, error: &Error, context: &ErrorContext) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((error.to_string(), context.clone()));
+    }
+}
+
+#[derive(Debug)]
+struct Failing;
+
+impl Failing {
+    fn constructor(_: GitHubHost, _: AppId, _: PrivateKey) -> Box<dyn Workflow> {
+        Box::new(Failing)
+    }
+}
+
+impl Workflow for Failing {
+    fn initial_step(&self) -> Box<dyn Step> {
+        Box::new(FailingStep)
+    }
+}
+
+struct FailingStep;
+
+#[async_trait]
+impl Step for FailingStep {
+    async fn next(self: Box<Self>, _state: &mut State) -> Result<Transition, WorkflowError> {
+        Err(WorkflowError::UnexpectedError(anyhow!(
+            "check run is missing"
+        )))
+    }
+}
+
+async fn send_delivery(signature: &str, reporter: RecordingReporter) -> Result<(), Error> {
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .error_reporter(reporter)?
+        .workflow(Failing::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let fixture = format!(
+        "{}/tests/fixtures/check_run.created.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+
+    Client::new()
+        .post(format!("http://{}/", addr))
+        .header("X-GitHub-Event", "not_a_real_event")
+        .header("X-GitHub-Delivery", "72d3162e-cc78-11e3-81ab-4c9367dc0958")
+        .header("X-Hub-Signature-256", signature)
+        .body(read(fixture).unwrap())
+        .send()
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn workflow_error_is_reported_with_delivery_context() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let reporter = RecordingReporter::default();
+    send_delivery(
+        "sha256=ba9f77aa6bc9740e9be7f68e4e21a64821cc5b59fd286d409d605a0b8affe7ff",
+        reporter.clone(),
+    )
+    .await?;
+
+    let reports = reporter.0.lock().unwrap();
+    assert_eq!(1, reports.len());
+
+    let (error, context) = &reports[0];
+    assert_eq!("check run is missing", error);
+    assert_eq!(
+        Some("72d3162e-cc78-11e3-81ab-4c9367dc0958"),
+        context.delivery_id.as_deref()
+    );
+    assert_eq!(Some("not_a_real_event"), context.event.as_deref());
+    assert_eq!(Some("created"), context.action.as_deref());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_signature_is_not_reported() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let reporter = RecordingReporter::default();
+    send_delivery(
+        "sha256=0000000000000000000000000000000000000000000000000000000000000000",
+        reporter.clone(),
+    )
+    .await?;
+
+    assert!(reporter.0.lock().unwrap().is_empty());
+
+    Ok(())
+}
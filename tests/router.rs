@@ -0,0 +1,98 @@
+use std::fs::read;
+use std::net::{SocketAddr, TcpListener};
+
+use axum::http::{HeaderValue, Request};
+use axum::middleware::{from_fn, Next};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Router, Server};
+use reqwest::Client;
+
+use octox::{Error, Octox};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+#[tokio::test]
+async fn router_can_be_nested() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?;
+
+    let app = Router::new().nest("/github", octox.router()?);
+
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    });
+
+    let fixture = format!(
+        "{}/tests/fixtures/check_run.created.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let body = read(fixture).unwrap();
+
+    let response = Client::new()
+        .post(format!("http://{}/github", addr))
+        .header("X-GitHub-Event", "not_a_real_event")
+        .header(
+            "X-Hub-Signature-256",
+            "sha256=ba9f77aa6bc9740e9be7f68e4e21a64821cc5b59fd286d409d605a0b8affe7ff",
+        )
+        .body(body)
+        .send()
+        .await?;
+
+    assert_eq!(
+        response.text().await.unwrap(),
+        "\"received unsupported event\""
+    );
+    Ok(())
+}
+
+async fn add_header<B>(request: Request<B>, next: Next<B>) -> impl IntoResponse {
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert("x-octox", HeaderValue::from_static("custom"));
+    response
+}
+
+#[tokio::test]
+async fn custom_routes_and_layers_are_served() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .route("/status", get(|| async { "running" }))?
+        .layer(from_fn(add_header))?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let response = Client::new()
+        .get(format!("http://{}/status", addr))
+        .send()
+        .await?;
+
+    assert_eq!("custom", response.headers()["x-octox"]);
+    assert_eq!("running", response.text().await.unwrap());
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
+use reqwest::Client;
+use tokio::sync::oneshot;
+
+use octox::{Delivery, Error, Octox, State, Step, Transition, Workflow, WorkflowError};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+/// Ids of the deliveries whose workflows have started
+static STARTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[derive(Debug)]
+struct Slow;
+
+impl Slow {
+    fn constructor(_: GitHubHost, _: AppId, _: PrivateKey) -> Box<dyn Workflow> {
+        Box::new(Slow)
+    }
+}
+
+impl Workflow for Slow {
+    fn initial_step(&self) -> Box<dyn Step> {
+        Box::new(SlowStep)
+    }
+}
+
+struct SlowStep;
+
+#[async_trait]
+impl Step for SlowStep {
+    async fn next(self: Box<Self>, state: &mut State) -> Result<Transition, WorkflowError> {
+        let delivery: &Delivery = state.get().unwrap();
+        STARTED
+            .lock()
+            .unwrap()
+            .push(delivery.id.clone().unwrap_or_default());
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        Ok(Transition::Complete("finished".into()))
+    }
+}
+
+fn serve_slow(timeout: Duration) -> Result<(SocketAddr, oneshot::Sender<()>, Octox), Error> {
+    let listener = TcpListener::bind("127.0.0.1:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .webhook_secret("secret")?
+        .shutdown_signal(async {
+            signal.await.ok();
+        })?
+        .shutdown_timeout(timeout)?
+        .workflow(Slow::constructor)?;
+
+    Ok((addr, shutdown, octox))
+}
+
+async fn send_delivery(addr: SocketAddr, delivery_id: &str) -> reqwest::Result<reqwest::Response> {
+    let body = std::fs::read(format!(
+        "{}/tests/fixtures/check_run.created.json",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap();
+
+    Client::new()
+        .post(format!("http://{}/", addr))
+        .header("X-GitHub-Event", "check_run")
+        .header("X-GitHub-Delivery", delivery_id)
+        .header(
+            "X-Hub-Signature-256",
+            "sha256=ba9f77aa6bc9740e9be7f68e4e21a64821cc5b59fd286d409d605a0b8affe7ff",
+        )
+        .body(body)
+        .send()
+        .await
+}
+
+async fn wait_until_started(delivery_id: &str) {
+    while !STARTED.lock().unwrap().iter().any(|id| id == delivery_id) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn serve_returns_after_shutdown_signal() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .webhook_secret("secret")?
+        .shutdown_signal(async {
+            signal.await.ok();
+        })?
+        .shutdown_timeout(Duration::from_secs(1))?
+        .workflow(HelloWorld::constructor)?;
+
+    let server = tokio::spawn(octox.serve());
+
+    shutdown.send(()).unwrap();
+
+    let abandoned = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not shut down")
+        .unwrap()?;
+
+    assert!(abandoned.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn serve_drains_running_workflow_before_shutdown() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let (addr, shutdown, octox) = serve_slow(Duration::from_secs(5))?;
+    let server = tokio::spawn(octox.serve());
+
+    let request = tokio::spawn(send_delivery(addr, "drained"));
+    wait_until_started("drained").await;
+
+    shutdown.send(()).unwrap();
+
+    let response = request.await.unwrap()?;
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!("\"finished\"", response.text().await?);
+
+    let abandoned = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not shut down")
+        .unwrap()?;
+
+    assert!(abandoned.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn serve_returns_deliveries_abandoned_at_timeout() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let (addr, shutdown, octox) = serve_slow(Duration::from_millis(50))?;
+    let server = tokio::spawn(octox.serve());
+
+    let request = tokio::spawn(send_delivery(addr, "abandoned"));
+    wait_until_started("abandoned").await;
+
+    shutdown.send(()).unwrap();
+
+    let abandoned = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not shut down")
+        .unwrap()?;
+
+    assert_eq!(1, abandoned.len());
+    assert_eq!(Some("abandoned".into()), abandoned[0].id);
+    assert_eq!("check_run", abandoned[0].event);
+
+    request.abort();
+    Ok(())
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "otel")]
+
+use std::time::Duration;
+
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
+use mockito::{mock, Matcher};
+use serde_json::Value;
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
+
+use octox::client::GitHubClient;
+use octox::Error;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_sends_trace_context_to_github() -> Result<(), Error> {
+    let subscriber = tracing_subscriber::registry().with(octox::telemetry::layer("octox-test")?);
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let app = mock("GET", "/app")
+        .match_header(
+            "traceparent",
+            Matcher::Regex("^00-[0-9a-f]{32}-[0-9a-f]{16}-0[01]$".into()),
+        )
+        .with_status(200)
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let client = GitHubClient::new(
+        GitHubHost::new(mockito::server_url()),
+        AppId::new(1),
+        PrivateKey::new(include_str!("fixtures/private-key.pem").into()),
+    )
+    .max_wait(Duration::from_secs(0));
+
+    client
+        .get::<Value>("app")
+        .instrument(tracing::info_span!("delivery"))
+        .await?;
+
+    app.assert();
+    tokio::task::spawn_blocking(octox::telemetry::shutdown)
+        .await
+        .unwrap();
+    Ok(())
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "testing")]
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use github_parts::github::WebhookSecret;
+use serde_json::json;
+use tower::ServiceExt;
+
+use octox::testing::{Fixture, WorkflowHarness};
+use octox::{Error, Octox, State, Step, Transition, Workflow, WorkflowError};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+fn fixture(event_type: &str) -> Result<Fixture, Error> {
+    Fixture::load(
+        event_type,
+        format!(
+            "{}/tests/fixtures/check_run.created.json",
+            env!("CARGO_MANIFEST_DIR")
+        ),
+    )
+}
+
+#[derive(Debug)]
+struct Countdown;
+
+#[async_trait]
+impl Workflow for Countdown {
+    fn initial_step(&self) -> Box<dyn Step> {
+        Box::new(Decrement)
+    }
+}
+
+struct Decrement;
+
+#[async_trait]
+impl Step for Decrement {
+    async fn next(self: Box<Self>, state: &mut State) -> Result<Transition, WorkflowError> {
+        let count: &mut u32 = state
+            .get_mut()
+            .ok_or_else(|| WorkflowError::MissingData("count".into()))?;
+
+        if *count == 0 {
+            return Ok(Transition::Complete(json!("liftoff")));
+        }
+
+        *count -= 1;
+        Ok(Transition::Next(Box::new(Decrement)))
+    }
+}
+
+#[tokio::test]
+async fn harness_runs_workflow_with_fixture() -> Result<(), Error> {
+    let run = WorkflowHarness::new(&HelloWorld)
+        .fixture(&fixture("not_a_real_event")?)?
+        .run()
+        .await;
+
+    run.assert_steps(&["HelloWorldStep"]);
+    run.assert_completed_with(json!("received unsupported event"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn harness_runs_workflow_with_preloaded_state() {
+    let run = WorkflowHarness::new(&Countdown).state(2u32).run().await;
+
+    run.assert_steps(&["Decrement", "Decrement", "Decrement"]);
+    run.assert_completed_with(json!("liftoff"));
+    assert_eq!(Some(&0), run.state.get::<u32>());
+}
+
+#[tokio::test]
+async fn harness_reports_workflow_errors() {
+    let run = WorkflowHarness::new(&Countdown).run().await;
+
+    run.assert_steps(&["Decrement"]);
+    run.assert_failed_with("missing_data");
+}
+
+#[tokio::test]
+async fn signed_request_is_accepted_by_router() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let router = Octox::new()
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?
+        .router()?;
+
+    let request = fixture("not_a_real_event")?
+        .delivery_id("72d3162e-cc78-11e3-81ab-4c9367dc0958")
+        .signed_request("/", &WebhookSecret::new("secret".into()))?;
+    let response = router.oneshot(request).await.unwrap();
+
+    assert_eq!(StatusCode::OK, response.status());
+    Ok(())
+}
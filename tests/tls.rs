@@ -0,0 +1,107 @@
+#![cfg(feature = "tls")]
+
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+
+use axum::routing::get;
+use reqwest::{redirect, Client, StatusCode};
+
+use octox::{Error, Octox};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+fn self_signed_certificate(name: &str) -> (PathBuf, PathBuf) {
+    let certificate = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+
+    let certificate_path = std::env::temp_dir().join(format!("{}.crt", name));
+    let key_path = std::env::temp_dir().join(format!("{}.key", name));
+
+    std::fs::write(&certificate_path, certificate.serialize_pem().unwrap()).unwrap();
+    std::fs::write(&key_path, certificate.serialize_private_key_pem()).unwrap();
+
+    (certificate_path, key_path)
+}
+
+fn free_address() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn tls_serves_https() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let (certificate_path, key_path) = self_signed_certificate("octox-tls-serves");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .webhook_secret("secret")?
+        .tls(&certificate_path, &key_path)?
+        .route("/status", get(|| async { "running" }))?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let response = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?
+        .get(format!("https://localhost:{}/status", port))
+        .send()
+        .await?;
+
+    assert_eq!("running", response.text().await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn tls_redirects_http_to_https() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let (certificate_path, key_path) = self_signed_certificate("octox-tls-redirects");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let redirect_address = free_address();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .webhook_secret("secret")?
+        .tls(&certificate_path, &key_path)?
+        .tls_redirect(redirect_address)?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let client = Client::builder()
+        .redirect(redirect::Policy::none())
+        .build()?;
+    let url = format!("http://localhost:{}/health", redirect_address.port());
+
+    // The redirect listener is bound after the server has started
+    let mut attempts = 0;
+    let response = loop {
+        match client.get(&url).send().await {
+            Err(error) if error.is_connect() && attempts < 50 => {
+                attempts += 1;
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            result => break result?,
+        }
+    };
+
+    assert_eq!(StatusCode::PERMANENT_REDIRECT, response.status());
+    assert_eq!(
+        format!("https://localhost:{}/health", port),
+        response.headers()["location"]
+    );
+    Ok(())
+}
@@ -0,0 +1,66 @@
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixListener;
+use std::time::Duration;
+
+use axum::routing::get;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use octox::{Error, Octox};
+
+use self::workflow::HelloWorld;
+
+mod workflow;
+
+#[tokio::test]
+async fn unix_socket_replaces_stale_socket() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let path = std::env::temp_dir().join("octox-unix-socket.sock");
+    std::fs::remove_file(&path).ok();
+
+    // Leave a stale socket file behind
+    drop(UnixListener::bind(&path).unwrap());
+
+    let octox = Octox::new()
+        .unix_socket(&path)?
+        .unix_socket_mode(0o660)?
+        .webhook_secret("secret")?
+        .route("/status", get(|| async { "running" }))?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let mut stream = connect(&path).await;
+    stream
+        .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.ends_with("running"), "{}", response);
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(0o660, mode & 0o777);
+
+    Ok(())
+}
+
+async fn connect(path: &std::path::Path) -> UnixStream {
+    for _ in 0..50 {
+        if let Ok(stream) = UnixStream::connect(path).await {
+            return stream;
+        }
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    panic!("failed to connect to {}", path.display());
+}
@@ -1,9 +1,13 @@
 use std::fs::read;
 use std::net::{SocketAddr, TcpListener};
 
+use async_trait::async_trait;
+use github_parts::github::app::AppId;
+use github_parts::github::{GitHubHost, PrivateKey};
 use reqwest::Client;
+use serde_json::Value;
 
-use octox::{Error, Octox};
+use octox::{Error, Octox, State, Step, Transition, Workflow, WorkflowError};
 
 use self::workflow::HelloWorld;
 
@@ -131,3 +135,152 @@ async fn webhook_rejects_invalid_signature() -> Result<(), Error> {
         .contains("X-Hub-Signature-256 header is invalid"));
     Ok(())
 }
+
+#[tokio::test]
+async fn webhook_returns_problem_details() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let response = Client::new()
+        .post(format!("http://{}/", addr))
+        .header("X-GitHub-Delivery", "72d3162e-cc78-11e3-81ab-4c9367dc0958")
+        .header(
+            "X-Hub-Signature-256",
+            "sha256=21fc0cdd18aa13806dec49fa657a57571704de8690eaeda53c103493d55d6a37",
+        )
+        .body("{}")
+        .send()
+        .await?;
+
+    assert_eq!(401, response.status().as_u16());
+    assert_eq!(
+        "application/problem+json",
+        response.headers()["content-type"]
+    );
+
+    let problem: Value = response.json().await.unwrap();
+    assert_eq!("invalid_signature", problem["code"]);
+    assert_eq!(401, problem["status"]);
+    assert_eq!(
+        "72d3162e-cc78-11e3-81ab-4c9367dc0958",
+        problem["delivery_id"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn webhook_rejects_malformed_payload() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(HelloWorld::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let response = Client::new()
+        .post(format!("http://{}/", addr))
+        .header("X-GitHub-Event", "check_run")
+        .header(
+            "X-Hub-Signature-256",
+            "sha256=06eeaa3cb21a7b5b8bbec6d16de9a8827e9078cf0ec6dd05c0f93c905bc523fa",
+        )
+        .body(r#"{"action":"#)
+        .send()
+        .await?;
+
+    assert_eq!(400, response.status().as_u16());
+
+    let problem: Value = response.json().await.unwrap();
+    assert_eq!("invalid_payload", problem["code"]);
+    assert!(problem["detail"]
+        .as_str()
+        .unwrap()
+        .starts_with("failed to deserialize incoming request payload: EOF while parsing"));
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Skipping;
+
+impl Skipping {
+    fn constructor(_: GitHubHost, _: AppId, _: PrivateKey) -> Box<dyn Workflow> {
+        Box::new(Skipping)
+    }
+}
+
+impl Workflow for Skipping {
+    fn initial_step(&self) -> Box<dyn Step> {
+        Box::new(SkippingStep)
+    }
+}
+
+struct SkippingStep;
+
+#[async_trait]
+impl Step for SkippingStep {
+    async fn next(self: Box<Self>, _state: &mut State) -> Result<Transition, WorkflowError> {
+        Err(WorkflowError::Skipped("check run is not ours".into()))
+    }
+}
+
+#[tokio::test]
+async fn webhook_accepts_skipped_delivery() -> Result<(), Error> {
+    dotenv::dotenv().ok();
+
+    let listener = TcpListener::bind("0.0.0.0:0".parse::<SocketAddr>().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let octox = Octox::new()
+        .tcp_listener(listener)?
+        .github_host(mockito::server_url())?
+        .webhook_secret("secret")?
+        .workflow(Skipping::constructor)?;
+
+    tokio::spawn(async move {
+        octox.serve().await.unwrap();
+    });
+
+    let fixture = format!(
+        "{}/tests/fixtures/check_run.created.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+
+    let response = Client::new()
+        .post(format!("http://{}/", addr))
+        .header("X-GitHub-Event", "not_a_real_event")
+        .header(
+            "X-Hub-Signature-256",
+            "sha256=ba9f77aa6bc9740e9be7f68e4e21a64821cc5b59fd286d409d605a0b8affe7ff",
+        )
+        .body(read(fixture).unwrap())
+        .send()
+        .await?;
+
+    assert_eq!(202, response.status().as_u16());
+    assert_eq!("application/json", response.headers()["content-type"]);
+
+    let outcome: Value = response.json().await.unwrap();
+    assert_eq!("skipped", outcome["code"]);
+    assert_eq!("skipped: check run is not ours", outcome["detail"]);
+    Ok(())
+}